@@ -0,0 +1,120 @@
+//! Throughput of pattern matching and `convert_text` as the keymap grows.
+//!
+//! Run with `cargo bench --bench convert`. Each row pads the default keymap
+//! with synthetic multi-letter patterns. Before timing anything it checks
+//! that the trie splits the input into the same matches as a linear scan
+//! over every pattern, the way `convert_text` used to match, and that the
+//! padding leaves the conversion unchanged. The timings are indicative only.
+
+use bengali_keyboard::trie::Trie;
+use bengali_keyboard::{BengaliChar, BengaliKeyboard, KeyMap};
+use std::hint::black_box;
+use std::time::Instant;
+
+const SAMPLE: &str = "amar sonar bangla ami tOmay bhalobasi chirodin tOmar akash tOmar batas";
+const ROUNDS: usize = 2_000;
+/// The linear scan is slow enough with thousands of rules to need fewer.
+const LINEAR_ROUNDS: usize = 20;
+
+/// The default keymap plus `extra` deterministic 3- and 4-letter patterns
+/// spread across the alphabet, leaving out any that occur in `input` so the
+/// conversion stays the same.
+fn padded_keymap(extra: usize, input: &str) -> KeyMap {
+    let mut keymap = KeyMap::new();
+    let alphabet: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+
+    for n in 0..extra {
+        let mut pattern = String::new();
+        let mut rest = n;
+        for _ in 0..3 + n % 2 {
+            pattern.push(alphabet[rest % alphabet.len()]);
+            rest = rest / alphabet.len() + 7;
+        }
+        if input.contains(&pattern) {
+            continue;
+        }
+        keymap.patterns.entry(pattern).or_insert(BengaliChar {
+            bengali: "ক্ষ".to_string(),
+            is_consonant: true,
+            is_vowel: false,
+        });
+    }
+
+    keymap
+}
+
+/// Greedy longest matches as (start, length), skipping unmatched chars.
+fn split(input: &[char], longest_at: impl Fn(&[char]) -> Option<usize>) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < input.len() {
+        match longest_at(&input[i..]) {
+            Some(len) => {
+                matches.push((i, len));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+fn ns_per_char(rounds: usize, chars: usize, run: impl Fn()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        run();
+    }
+    start.elapsed().as_nanos() as f64 / (rounds * chars) as f64
+}
+
+fn main() {
+    let input = SAMPLE.repeat(4);
+    let chars: Vec<char> = input.chars().collect();
+    let expected = BengaliKeyboard::new().convert_text(&input);
+
+    println!(
+        "{:>8} {:>16} {:>16} {:>16}",
+        "rules", "trie ns/char", "linear ns/char", "convert ns/char"
+    );
+    for extra in [0, 1_000, 5_000, 20_000] {
+        let keymap = padded_keymap(extra, &input);
+        let mut trie = Trie::new();
+        for pattern in keymap.patterns.keys() {
+            *trie.entry_or_insert_with(pattern, || 0) = pattern.chars().count();
+        }
+        let patterns: Vec<Vec<char>> = keymap
+            .patterns
+            .keys()
+            .map(|pattern| pattern.chars().collect())
+            .collect();
+        let rules = patterns.len();
+
+        let trie_longest = |rest: &[char]| trie.longest_match(rest).map(|(&len, _)| len);
+        let linear_longest = |rest: &[char]| {
+            patterns
+                .iter()
+                .filter(|pattern| rest.starts_with(pattern))
+                .map(Vec::len)
+                .max()
+        };
+        assert_eq!(
+            split(&chars, trie_longest),
+            split(&chars, linear_longest),
+            "{rules} rules"
+        );
+
+        let keyboard = BengaliKeyboard::with_keymap(keymap);
+        assert_eq!(keyboard.convert_text(&input), expected, "{rules} rules");
+
+        let trie_ns = ns_per_char(ROUNDS, chars.len(), || {
+            black_box(split(black_box(&chars), trie_longest));
+        });
+        let linear_ns = ns_per_char(LINEAR_ROUNDS, chars.len(), || {
+            black_box(split(black_box(&chars), linear_longest));
+        });
+        let convert_ns = ns_per_char(ROUNDS, chars.len(), || {
+            black_box(keyboard.convert_text(black_box(&input)));
+        });
+        println!("{rules:>8} {trie_ns:>16.1} {linear_ns:>16.1} {convert_ns:>16.1}");
+    }
+}
@@ -0,0 +1,132 @@
+//! Linux key codes (`linux/input-event-codes.h`) and the characters they
+//! type on a US keyboard.
+
+use bengali_keyboard::hotkey::Key;
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const SYN_REPORT: u16 = 0;
+
+pub const KEY_ESC: u16 = 1;
+pub const KEY_BACKSPACE: u16 = 14;
+pub const KEY_TAB: u16 = 15;
+pub const KEY_U: u16 = 22;
+pub const KEY_ENTER: u16 = 28;
+pub const KEY_LEFTCTRL: u16 = 29;
+pub const KEY_A: u16 = 30;
+pub const KEY_LEFTSHIFT: u16 = 42;
+pub const KEY_Z: u16 = 44;
+pub const KEY_RIGHTSHIFT: u16 = 54;
+pub const KEY_LEFTALT: u16 = 56;
+pub const KEY_SPACE: u16 = 57;
+pub const KEY_CAPSLOCK: u16 = 58;
+pub const KEY_F10: u16 = 68;
+pub const KEY_RIGHTCTRL: u16 = 97;
+pub const KEY_RIGHTALT: u16 = 100;
+pub const KEY_LEFTMETA: u16 = 125;
+pub const KEY_RIGHTMETA: u16 = 126;
+/// Highest code of an ordinary key; buttons and the like come after.
+pub const KEY_MAX_KEYBOARD: u16 = 248;
+
+/// Runs of consecutive codes: the first code, then what the keys type
+/// without and with Shift.
+const ROWS: [(u16, &str, &str); 4] = [
+    (2, "1234567890-=", "!@#$%^&*()_+"),
+    (16, "qwertyuiop[]", "QWERTYUIOP{}"),
+    (30, "asdfghjkl;'`", "ASDFGHJKL:\"~"),
+    (43, "\\zxcvbnm,./", "|ZXCVBNM<>?"),
+];
+
+/// What `code` types, with [`BACKSPACE`] for the backspace key. Caps Lock
+/// only shifts letters.
+///
+/// [`BACKSPACE`]: bengali_keyboard::BACKSPACE
+pub fn key_char(code: u16, shift: bool, caps_lock: bool) -> Option<char> {
+    match code {
+        KEY_SPACE => return Some(' '),
+        KEY_ENTER => return Some('\n'),
+        KEY_TAB => return Some('\t'),
+        KEY_BACKSPACE => return Some(bengali_keyboard::BACKSPACE),
+        _ => {}
+    }
+    ROWS.iter().find_map(|&(first, plain, shifted)| {
+        let index = usize::from(code.checked_sub(first)?);
+        let ch = plain.chars().nth(index)?;
+        let shift = if ch.is_ascii_alphabetic() {
+            shift != caps_lock
+        } else {
+            shift
+        };
+        if shift {
+            shifted.chars().nth(index)
+        } else {
+            Some(ch)
+        }
+    })
+}
+
+/// The key that types `ch` and whether it needs Shift, the inverse of
+/// [`key_char`] without Caps Lock.
+pub fn char_key(ch: char) -> Option<(u16, bool)> {
+    match ch {
+        ' ' => return Some((KEY_SPACE, false)),
+        '\n' => return Some((KEY_ENTER, false)),
+        '\t' => return Some((KEY_TAB, false)),
+        _ => {}
+    }
+    ROWS.iter().find_map(|&(first, plain, shifted)| {
+        let code = |index: usize| first + index as u16;
+        plain
+            .chars()
+            .position(|key| key == ch)
+            .map(|index| (code(index), false))
+            .or_else(|| {
+                shifted
+                    .chars()
+                    .position(|key| key == ch)
+                    .map(|index| (code(index), true))
+            })
+    })
+}
+
+pub fn is_shift(code: u16) -> bool {
+    matches!(code, KEY_LEFTSHIFT | KEY_RIGHTSHIFT)
+}
+
+/// Ctrl, Alt (not AltGr) and Meta, which turn keys into shortcuts.
+pub fn is_shortcut_modifier(code: u16) -> bool {
+    matches!(
+        code,
+        KEY_LEFTCTRL | KEY_RIGHTCTRL | KEY_LEFTALT | KEY_LEFTMETA | KEY_RIGHTMETA
+    )
+}
+
+/// Shift, Caps Lock, AltGr and the shortcut modifiers, which change other
+/// keys rather than type anything themselves.
+pub fn is_modifier(code: u16) -> bool {
+    is_shift(code) || is_shortcut_modifier(code) || matches!(code, KEY_CAPSLOCK | KEY_RIGHTALT)
+}
+
+/// The key `code` is for hotkeys. Right Alt is AltGr, not a hotkey
+/// modifier.
+pub fn hotkey_key(code: u16) -> Option<Key> {
+    match code {
+        KEY_ESC => Some(Key::Escape),
+        // F1 to F10, F11 and F12, then F13 to F24
+        59..=68 => Some(Key::F((code - 58) as u8)),
+        87 | 88 => Some(Key::F((code - 76) as u8)),
+        183..=194 => Some(Key::F((code - 170) as u8)),
+        KEY_LEFTSHIFT | KEY_RIGHTSHIFT => Some(Key::Shift),
+        KEY_LEFTCTRL | KEY_RIGHTCTRL => Some(Key::Ctrl),
+        KEY_LEFTALT => Some(Key::Alt),
+        KEY_LEFTMETA | KEY_RIGHTMETA => Some(Key::Super),
+        _ => key_char(code, false, false).and_then(Key::from_char),
+    }
+}
+
+/// The hotkey modifier mask for the modifier keys in `held`.
+pub fn hotkey_modifiers<'a>(held: impl IntoIterator<Item = &'a u16>) -> u8 {
+    held.into_iter()
+        .filter_map(|&code| hotkey_key(code))
+        .fold(0, |mask, key| mask | key.modifier_mask())
+}
@@ -0,0 +1,290 @@
+//! Bengali typing for Linux consoles and compositors without input method
+//! support.
+//!
+//! The daemon grabs a keyboard through evdev, so nothing else sees its keys,
+//! and runs them through the same [`KeyboardState`] as the Windows hook.
+//! Keys the state machine lets through are passed on unchanged through a
+//! uinput virtual keyboard; converted text is typed on that keyboard by
+//! Unicode entry: Ctrl+Shift+U, the code point in hex, then space, which GTK,
+//! Qt with IBus and most terminals understand.
+//!
+//! The hotkeys (F10 by default) toggle conversion, type the next word in
+//! English and switch layouts; converting the selection needs an input
+//! method and is not available here. Ctrl, Alt and Meta shortcuts pass
+//! through and end the word; Right Alt is AltGr for the fixed layouts.
+
+pub mod keys;
+pub mod uinput;
+
+use bengali_keyboard::{config, BengaliKeyboard, History, HotkeyAction, Hotkeys, InputSink, KeyboardState};
+use keys::{
+    EV_KEY, EV_SYN, KEY_BACKSPACE, KEY_CAPSLOCK, KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_RIGHTALT, KEY_SPACE, KEY_U,
+    SYN_REPORT,
+};
+use std::collections::{BTreeSet, HashSet};
+use std::io;
+use std::time::Instant;
+
+/// One evdev input event, without its timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub kind: u16,
+    pub code: u16,
+    /// For keys 1 is a press, 2 an autorepeat and 0 a release.
+    pub value: i32,
+}
+
+impl Event {
+    pub fn key(code: u16, value: i32) -> Self {
+        Self {
+            kind: EV_KEY,
+            code,
+            value,
+        }
+    }
+
+    pub fn report() -> Self {
+        Self {
+            kind: EV_SYN,
+            code: SYN_REPORT,
+            value: 0,
+        }
+    }
+}
+
+/// Where the daemon's output goes: the virtual keyboard, or a `Vec` in
+/// tests.
+pub trait EventSink {
+    fn emit(&mut self, events: &[Event]) -> io::Result<()>;
+}
+
+impl EventSink for Vec<Event> {
+    fn emit(&mut self, events: &[Event]) -> io::Result<()> {
+        self.extend_from_slice(events);
+        Ok(())
+    }
+}
+
+/// A key change followed by the report that delivers it.
+fn push_key(events: &mut Vec<Event>, code: u16, value: i32) {
+    events.push(Event::key(code, value));
+    events.push(Event::report());
+}
+
+fn push_tap(events: &mut Vec<Event>, code: u16) {
+    push_key(events, code, 1);
+    push_key(events, code, 0);
+}
+
+/// Collects what [`KeyboardState`] writes as key events for the virtual
+/// keyboard.
+#[derive(Default)]
+struct Typist {
+    events: Vec<Event>,
+}
+
+impl InputSink for Typist {
+    fn send_backspaces(&mut self, count: usize) {
+        for _ in 0..count {
+            push_tap(&mut self.events, KEY_BACKSPACE);
+        }
+    }
+
+    fn send_text(&mut self, text: &str) {
+        for ch in text.chars() {
+            push_key(&mut self.events, KEY_LEFTCTRL, 1);
+            push_key(&mut self.events, KEY_LEFTSHIFT, 1);
+            push_tap(&mut self.events, KEY_U);
+            push_key(&mut self.events, KEY_LEFTSHIFT, 0);
+            push_key(&mut self.events, KEY_LEFTCTRL, 0);
+            for digit in format!("{:x}", ch as u32).chars() {
+                if let Some((code, _)) = keys::char_key(digit) {
+                    push_tap(&mut self.events, code);
+                }
+            }
+            push_tap(&mut self.events, KEY_SPACE);
+        }
+    }
+}
+
+/// Turns events from the grabbed keyboard into events for the virtual one.
+pub struct Translator {
+    keyboard: BengaliKeyboard,
+    /// The layout's name, for the switch-layout hotkey to move on from.
+    layout: String,
+    state: KeyboardState,
+    /// Spellings picked from suggestions in the other front-ends.
+    history: History,
+    hotkeys: Hotkeys,
+    /// Modifier keys down, as passed on to the virtual keyboard.
+    modifiers: BTreeSet<u16>,
+    caps_lock: bool,
+    /// Keys whose press was swallowed, so their release is too.
+    swallowed: HashSet<u16>,
+}
+
+impl Translator {
+    /// A translator for `keyboard`, with conversion on.
+    pub fn new(keyboard: BengaliKeyboard) -> Self {
+        let mut state = KeyboardState::new();
+        state.enabled = true;
+        Self {
+            keyboard,
+            layout: "phonetic".to_string(),
+            state,
+            history: History::new(),
+            hotkeys: Hotkeys::default(),
+            modifiers: BTreeSet::new(),
+            caps_lock: false,
+            swallowed: HashSet::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.enabled
+    }
+
+    /// Replaces the default hotkeys, F10 to toggle.
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    /// Converts with the spellings in `history`, usually
+    /// [`config::load_history`]; empty unless set.
+    pub fn set_history(&mut self, history: History) {
+        self.history = history;
+    }
+
+    /// Names the keyboard's layout, one of [`config::layout_names`];
+    /// phonetic unless set.
+    pub fn set_layout_name(&mut self, name: &str) {
+        self.layout = name.to_string();
+    }
+
+    pub fn layout_name(&self) -> &str {
+        &self.layout
+    }
+
+    /// Does what a hotkey is bound to.
+    fn hotkey(&mut self, action: HotkeyAction, out: &mut impl EventSink) -> io::Result<()> {
+        match action {
+            HotkeyAction::Toggle => self.state.toggle(),
+            HotkeyAction::ForceEnglish => {
+                let mut typist = Typist::default();
+                self.state
+                    .force_english_word(&self.keyboard, &self.history, &mut typist);
+                self.type_out(typist.events, out)?;
+            }
+            HotkeyAction::SwitchLayout => {
+                let next = config::next_layout(&self.layout);
+                match config::load_keyboard(next) {
+                    Ok(keyboard) => {
+                        self.keyboard = keyboard;
+                        self.layout = next.to_string();
+                        self.state.reset_word();
+                    }
+                    Err(e) => eprintln!("bengali-evdev: {e}"),
+                }
+            }
+            // There is no way to read the selection from here
+            HotkeyAction::ConvertSelection => {}
+        }
+        Ok(())
+    }
+
+    /// Handles one event from the grabbed keyboard. Only key events are
+    /// passed on; the virtual keyboard reports each change itself.
+    pub fn handle(&mut self, event: Event, out: &mut impl EventSink) -> io::Result<()> {
+        if event.kind != EV_KEY {
+            return Ok(());
+        }
+        let Event { code, value, .. } = event;
+        let forward = |out: &mut dyn EventSink| {
+            let mut events = Vec::new();
+            push_key(&mut events, code, value);
+            out.emit(&events)
+        };
+
+        if value == 2 && self.swallowed.contains(&code) {
+            return Ok(());
+        }
+        if let (Some(key), 0 | 1) = (keys::hotkey_key(code), value) {
+            let modifiers = keys::hotkey_modifiers(&self.modifiers);
+            if let Some(action) = self.hotkeys.key(key, modifiers, value == 1, Instant::now()) {
+                self.hotkey(action, out)?;
+                if value == 1 {
+                    self.swallowed.insert(code);
+                    return Ok(());
+                }
+            }
+        }
+
+        if value == 0 {
+            self.modifiers.remove(&code);
+            if self.swallowed.remove(&code) {
+                return Ok(());
+            }
+            return forward(out);
+        }
+        if keys::is_shift(code) || keys::is_shortcut_modifier(code) || code == KEY_RIGHTALT {
+            self.modifiers.insert(code);
+            return forward(out);
+        }
+        if code == KEY_CAPSLOCK {
+            if value == 1 {
+                self.caps_lock = !self.caps_lock;
+            }
+            return forward(out);
+        }
+        // Ctrl, Alt and Meta shortcuts go to the application and end the word
+        if self.modifiers.iter().any(|&held| keys::is_shortcut_modifier(held)) {
+            self.state.reset_word();
+            return forward(out);
+        }
+        let shift = self.modifiers.iter().any(|&held| keys::is_shift(held));
+        let Some(ch) = keys::key_char(code, shift, self.caps_lock) else {
+            // Arrows, Home and the like move away from the word
+            self.state.reset_word();
+            return forward(out);
+        };
+
+        let mut typist = Typist::default();
+        let altgr = self.modifiers.contains(&KEY_RIGHTALT);
+        let suppressed = self
+            .state
+            .process_key(&self.keyboard, &self.history, ch, altgr, &mut typist);
+        self.type_out(typist.events, out)?;
+        if suppressed {
+            self.swallowed.insert(code);
+            return Ok(());
+        }
+        forward(out)
+    }
+
+    /// Passes everything typed on `keyboard` on to `out` until reading
+    /// fails, as it does when the keyboard is unplugged.
+    pub fn run(&mut self, keyboard: &mut uinput::Keyboard, out: &mut impl EventSink) -> io::Result<()> {
+        loop {
+            for event in keyboard.read_events()? {
+                self.handle(event, out)?;
+            }
+        }
+    }
+
+    /// Emits `events` with the modifiers the user is holding let go of
+    /// meanwhile, so Shift does not turn a backspace into Shift+Backspace.
+    fn type_out(&self, typed: Vec<Event>, out: &mut impl EventSink) -> io::Result<()> {
+        if typed.is_empty() {
+            return Ok(());
+        }
+        let mut events = Vec::new();
+        for &held in &self.modifiers {
+            push_key(&mut events, held, 0);
+        }
+        events.extend(typed);
+        for &held in &self.modifiers {
+            push_key(&mut events, held, 1);
+        }
+        out.emit(&events)
+    }
+}
@@ -0,0 +1,87 @@
+//! `bengali-evdev`: grabs a keyboard and types Bengali through a uinput
+//! virtual keyboard. Needs read access to the keyboard's /dev/input node
+//! and write access to /dev/uinput, usually by being in the `input` group.
+
+use bengali_keyboard::{config, History};
+use bengali_keyboard_evdev::uinput::{Keyboard, VirtualKeyboard};
+use bengali_keyboard_evdev::Translator;
+use std::env;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: bengali-evdev [OPTIONS]
+
+Grabs a keyboard and types Bengali on any Linux console or compositor,
+entering converted text as Ctrl+Shift+U and its code point. F10 toggles
+conversion, unless hotkeys.toml in the config directory says otherwise.
+
+Options:
+  -d, --device PATH    The keyboard's /dev/input/event* node (default: the
+                       first device with letter keys)
+  -l, --layout LAYOUT  phonetic (default), bijoy, national or probhat
+  -h, --help           Show this help";
+
+struct Options {
+    device: Option<String>,
+    layout: String,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        device: None,
+        layout: "phonetic".to_string(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "-d" | "--device" => options.device = Some(value(&arg)?),
+            "-l" | "--layout" => options.layout = value(&arg)?,
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let keyboard = config::load_keyboard(&options.layout).map_err(|e| e.to_string())?;
+    let hotkeys = config::load_hotkeys().map_err(|e| e.to_string())?;
+    let history = config::load_history().unwrap_or_else(|e| {
+        eprintln!("bengali-evdev: {e}, ignoring the history");
+        History::new()
+    });
+    let mut device = match &options.device {
+        Some(path) => Keyboard::open(path).map_err(|e| format!("{path}: {e}"))?,
+        None => Keyboard::find().map_err(|e| e.to_string())?,
+    };
+    let mut output = VirtualKeyboard::create().map_err(|e| format!("/dev/uinput: {e}"))?;
+
+    let device_error = |e: std::io::Error| format!("{}: {e}", device.path().display());
+    device.wait_for_release(Duration::from_secs(2)).map_err(device_error)?;
+    device.grab().map_err(device_error)?;
+    eprintln!(
+        "bengali-evdev: typing {} on {}",
+        options.layout,
+        device.name().unwrap_or_else(|_| device.path().display().to_string())
+    );
+
+    let mut translator = Translator::new(keyboard);
+    translator.set_layout_name(&options.layout);
+    translator.set_hotkeys(hotkeys);
+    translator.set_history(history);
+    translator.run(&mut device, &mut output).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(message) = result {
+        eprintln!("bengali-evdev: {message}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,256 @@
+//! The physical keyboard, read through evdev, and the virtual one the
+//! daemon types with, created through uinput.
+
+use crate::keys::{EV_KEY, EV_SYN, KEY_A, KEY_MAX_KEYBOARD, KEY_SPACE, KEY_Z};
+use crate::{Event, EventSink};
+use std::ffi::CStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the virtual keyboard, so it is never picked as the one to grab.
+pub const VIRTUAL_KEYBOARD_NAME: &str = "Bengali keyboard (virtual)";
+
+// ioctl numbers as _IOC() builds them on x86, ARM and RISC-V
+const IOC_NONE: u64 = 0;
+const IOC_WRITE: u64 = 1;
+const IOC_READ: u64 = 2;
+
+const fn ioc(dir: u64, kind: u8, nr: u64, size: usize) -> u64 {
+    (dir << 30) | ((size as u64) << 16) | ((kind as u64) << 8) | nr
+}
+
+const EVIOCGRAB: u64 = ioc(IOC_WRITE, b'E', 0x90, mem::size_of::<libc::c_int>());
+const UI_SET_EVBIT: u64 = ioc(IOC_WRITE, b'U', 100, mem::size_of::<libc::c_int>());
+const UI_SET_KEYBIT: u64 = ioc(IOC_WRITE, b'U', 101, mem::size_of::<libc::c_int>());
+const UI_DEV_SETUP: u64 = ioc(IOC_WRITE, b'U', 3, mem::size_of::<libc::uinput_setup>());
+const UI_DEV_CREATE: u64 = ioc(IOC_NONE, b'U', 1, 0);
+const UI_DEV_DESTROY: u64 = ioc(IOC_NONE, b'U', 2, 0);
+
+const fn eviocgname(len: usize) -> u64 {
+    ioc(IOC_READ, b'E', 0x06, len)
+}
+
+const fn eviocgkey(len: usize) -> u64 {
+    ioc(IOC_READ, b'E', 0x18, len)
+}
+
+const fn eviocgbit(kind: u16, len: usize) -> u64 {
+    ioc(IOC_READ, b'E', 0x20 + kind as u64, len)
+}
+
+const fn ui_get_sysname(len: usize) -> u64 {
+    ioc(IOC_READ, b'U', 44, len)
+}
+
+/// Enough bytes for a bit per key code.
+const KEY_BITS: usize = 0x300 / 8;
+
+fn ioctl(file: &File, request: u64, arg: usize) -> io::Result<libc::c_int> {
+    // SAFETY: every request above either takes an int by value or a pointer
+    // to a buffer of the size encoded in it, which the callers pass
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), request as libc::Ioctl, arg) };
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+fn bit_set(bits: &[u8], bit: u16) -> bool {
+    bits.get(usize::from(bit / 8))
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+fn to_raw(event: Event) -> libc::input_event {
+    // SAFETY: input_event is plain integers; a zero time is filled in by
+    // the kernel
+    let mut raw: libc::input_event = unsafe { mem::zeroed() };
+    raw.type_ = event.kind;
+    raw.code = event.code;
+    raw.value = event.value;
+    raw
+}
+
+/// A keyboard's `/dev/input/event*` node.
+pub struct Keyboard {
+    file: File,
+    path: PathBuf,
+}
+
+impl Keyboard {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self {
+            file: File::open(path)?,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// The first device that has letter keys, other than our own.
+    pub fn find() -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir("/dev/input")?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("event"))
+            })
+            .collect();
+        // event10 after event9
+        paths.sort_by_key(|path| (path.as_os_str().len(), path.clone()));
+
+        paths
+            .iter()
+            .filter_map(|path| Self::open(path).ok())
+            .find(|keyboard| {
+                keyboard.has_letter_keys() && keyboard.name().ok().as_deref() != Some(VIRTUAL_KEYBOARD_NAME)
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no keyboard found in /dev/input"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn name(&self) -> io::Result<String> {
+        let mut name = [0u8; 256];
+        ioctl(&self.file, eviocgname(name.len()), name.as_mut_ptr() as usize)?;
+        let name = CStr::from_bytes_until_nul(&name).unwrap_or_default();
+        Ok(name.to_string_lossy().into_owned())
+    }
+
+    /// Whether the device reports A to Z and space, which mice, power
+    /// buttons and the like do not.
+    pub fn has_letter_keys(&self) -> bool {
+        let mut bits = [0u8; KEY_BITS];
+        ioctl(&self.file, eviocgbit(EV_KEY, bits.len()), bits.as_mut_ptr() as usize).is_ok()
+            && (KEY_A..=KEY_Z).chain([KEY_SPACE]).all(|key| bit_set(&bits, key))
+    }
+
+    /// Waits up to `timeout` for every key to be up. Grabbing a keyboard
+    /// while a key is down, like the Enter that started the daemon, leaves
+    /// that key stuck for everyone else.
+    pub fn wait_for_release(&self, timeout: Duration) -> io::Result<()> {
+        let start = Instant::now();
+        loop {
+            let mut bits = [0u8; KEY_BITS];
+            ioctl(&self.file, eviocgkey(bits.len()), bits.as_mut_ptr() as usize)?;
+            if bits.iter().all(|&byte| byte == 0) || start.elapsed() >= timeout {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Takes the keyboard for ourselves: nothing else sees its events until
+    /// it is closed.
+    pub fn grab(&self) -> io::Result<()> {
+        ioctl(&self.file, EVIOCGRAB, 1).map(drop)
+    }
+
+    /// Blocks for the next batch of events.
+    pub fn read_events(&mut self) -> io::Result<Vec<Event>> {
+        const SIZE: usize = mem::size_of::<libc::input_event>();
+        let mut buffer = [0u8; SIZE * 64];
+        let read = self.file.read(&mut buffer)?;
+        Ok(buffer[..read - read % SIZE]
+            .chunks_exact(SIZE)
+            .map(|chunk| {
+                // SAFETY: the chunk is exactly one input_event written by the
+                // kernel; read_unaligned copes with the byte buffer
+                let raw: libc::input_event = unsafe { chunk.as_ptr().cast::<libc::input_event>().read_unaligned() };
+                Event {
+                    kind: raw.type_,
+                    code: raw.code,
+                    value: raw.value,
+                }
+            })
+            .collect())
+    }
+}
+
+/// A keyboard created through `/dev/uinput`; it goes away when dropped.
+pub struct VirtualKeyboard {
+    file: File,
+}
+
+impl VirtualKeyboard {
+    pub fn create() -> io::Result<Self> {
+        Self::create_named(VIRTUAL_KEYBOARD_NAME)
+    }
+
+    /// A virtual keyboard with every ordinary key, called `name`.
+    pub fn create_named(name: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().write(true).open("/dev/uinput")?;
+        ioctl(&file, UI_SET_EVBIT, usize::from(EV_KEY))?;
+        ioctl(&file, UI_SET_EVBIT, usize::from(EV_SYN))?;
+        for key in 1..=KEY_MAX_KEYBOARD {
+            ioctl(&file, UI_SET_KEYBIT, usize::from(key))?;
+        }
+
+        // SAFETY: uinput_setup is plain integers and bytes
+        let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+        setup.id.bustype = 0x06; // BUS_VIRTUAL
+        for (slot, byte) in setup
+            .name
+            .iter_mut()
+            .zip(name.bytes().take(libc::UINPUT_MAX_NAME_SIZE - 1))
+        {
+            *slot = byte as libc::c_char;
+        }
+        ioctl(&file, UI_DEV_SETUP, &setup as *const libc::uinput_setup as usize)?;
+        ioctl(&file, UI_DEV_CREATE, 0)?;
+        Ok(Self { file })
+    }
+
+    /// The `/dev/input/event*` node of the device, for reading back what it
+    /// types.
+    pub fn event_path(&self) -> io::Result<PathBuf> {
+        let mut sysname = [0u8; 64];
+        ioctl(&self.file, ui_get_sysname(sysname.len()), sysname.as_mut_ptr() as usize)?;
+        let sysname = CStr::from_bytes_until_nul(&sysname)
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let sys = Path::new("/sys/devices/virtual/input").join(sysname);
+        fs::read_dir(&sys)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .find(|name| name.starts_with("event"))
+            .map(|name| Path::new("/dev/input").join(name))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no event node under {}", sys.display()),
+                )
+            })
+    }
+}
+
+impl EventSink for VirtualKeyboard {
+    fn emit(&mut self, events: &[Event]) -> io::Result<()> {
+        let bytes: Vec<u8> = events
+            .iter()
+            .flat_map(|&event| {
+                let raw = to_raw(event);
+                // SAFETY: viewing a plain struct as its bytes
+                let bytes: &[u8] = unsafe {
+                    std::slice::from_raw_parts((&raw as *const libc::input_event).cast(), mem::size_of_val(&raw))
+                };
+                bytes.to_vec()
+            })
+            .collect();
+        self.file.write_all(&bytes)
+    }
+}
+
+impl Drop for VirtualKeyboard {
+    fn drop(&mut self) {
+        let _ = ioctl(&self.file, UI_DEV_DESTROY, 0);
+    }
+}
@@ -0,0 +1,280 @@
+//! Runs key events through the translator and reads what it types back the
+//! way a GTK text field would. The uinput test needs write access to
+//! /dev/uinput, so it is ignored unless asked for with
+//! `cargo test -p bengali_keyboard_evdev -- --ignored`.
+
+use bengali_keyboard::{BengaliKeyboard, FixedLayout, History, Hotkeys, BACKSPACE};
+use bengali_keyboard_evdev::keys::{
+    self, EV_KEY, KEY_BACKSPACE, KEY_F10, KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_RIGHTALT, KEY_RIGHTCTRL, KEY_RIGHTSHIFT,
+    KEY_SPACE, KEY_U,
+};
+use bengali_keyboard_evdev::uinput::{Keyboard, VirtualKeyboard};
+use bengali_keyboard_evdev::{Event, Translator};
+use std::fs::OpenOptions;
+use std::thread;
+use std::time::Duration;
+
+/// A text field that understands Ctrl+Shift+U Unicode entry.
+#[derive(Default)]
+struct Screen {
+    text: String,
+    /// Keys pressed with Ctrl, other than Unicode entry.
+    shortcuts: Vec<char>,
+    shift: bool,
+    ctrl: bool,
+    /// Hex digits typed since Ctrl+Shift+U.
+    entry: Option<String>,
+}
+
+impl Screen {
+    fn read(events: &[Event]) -> Self {
+        let mut screen = Self::default();
+        for event in events.iter().filter(|event| event.kind == EV_KEY) {
+            screen.key(event.code, event.value);
+        }
+        screen
+    }
+
+    fn key(&mut self, code: u16, value: i32) {
+        match code {
+            KEY_LEFTSHIFT | KEY_RIGHTSHIFT => self.shift = value != 0,
+            KEY_LEFTCTRL | KEY_RIGHTCTRL => self.ctrl = value != 0,
+            _ if value == 0 => {}
+            KEY_U if self.ctrl && self.shift => self.entry = Some(String::new()),
+            KEY_SPACE if self.entry.is_some() => {
+                let hex = self.entry.take().unwrap();
+                let ch = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                self.text
+                    .push(ch.unwrap_or_else(|| panic!("bad Unicode entry {hex:?}")));
+            }
+            _ if self.ctrl => self.shortcuts.extend(keys::key_char(code, false, false)),
+            KEY_BACKSPACE => {
+                assert!(!self.shift, "Shift held over a backspace");
+                self.text.pop();
+            }
+            _ => {
+                // Other modifiers type nothing
+                let Some(ch) = keys::key_char(code, self.shift, false) else {
+                    return;
+                };
+                match &mut self.entry {
+                    // Entry only takes lowercase hex; Shift would break it
+                    Some(hex) if ch.is_ascii_hexdigit() && !ch.is_ascii_uppercase() => hex.push(ch),
+                    Some(hex) => panic!("{ch:?} inside Unicode entry {hex:?}"),
+                    None => self.text.push(ch),
+                }
+            }
+        }
+    }
+}
+
+/// Press and release events typing `text` on a US keyboard, with Shift
+/// held for shifted characters.
+fn typing(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    for ch in text.chars() {
+        let (code, shift) = if ch == BACKSPACE {
+            (KEY_BACKSPACE, false)
+        } else {
+            keys::char_key(ch).expect("typeable")
+        };
+        if shift {
+            events.push(Event::key(KEY_LEFTSHIFT, 1));
+        }
+        events.push(Event::key(code, 1));
+        events.push(Event::key(code, 0));
+        if shift {
+            events.push(Event::key(KEY_LEFTSHIFT, 0));
+        }
+    }
+    events
+}
+
+fn translate(translator: &mut Translator, events: &[Event]) -> Vec<Event> {
+    let mut output = Vec::new();
+    for &event in events {
+        translator.handle(event, &mut output).unwrap();
+    }
+    output
+}
+
+fn phonetic() -> Translator {
+    Translator::new(BengaliKeyboard::new())
+}
+
+#[test]
+fn keys_map_to_us_characters_and_back() {
+    assert_eq!(keys::key_char(30, false, false), Some('a'));
+    assert_eq!(keys::key_char(30, false, true), Some('A'));
+    assert_eq!(keys::key_char(30, true, true), Some('a'));
+    assert_eq!(keys::key_char(2, false, true), Some('1'));
+    assert_eq!(keys::key_char(40, true, false), Some('"'));
+    for ch in (' '..='~').chain(['\n', '\t']) {
+        let (code, shift) = keys::char_key(ch).unwrap();
+        assert_eq!(keys::key_char(code, shift, false), Some(ch));
+    }
+}
+
+#[test]
+fn words_are_typed_by_unicode_entry() {
+    let output = translate(&mut phonetic(), &typing("ami banglay kor"));
+    assert_eq!(Screen::read(&output).text, "আমি বাংলায় কর");
+    // The roman keys themselves never reach the application
+    assert!(!output.iter().any(|event| event.code == 37), "K leaked: {output:?}");
+}
+
+#[test]
+fn words_use_the_history() {
+    let mut history = History::new();
+    history.record("ami", "আমিই");
+    let mut translator = phonetic();
+    translator.set_history(history);
+    let output = translate(&mut translator, &typing("ami kor "));
+    assert_eq!(Screen::read(&output).text, "আমিই কর ");
+
+    // Including the word the force-English hotkey finishes
+    translator.set_hotkeys(Hotkeys::from_toml("force_english = \"Shift Shift\"").unwrap());
+    let mut events = typing("ami");
+    events.extend([
+        Event::key(KEY_LEFTSHIFT, 1),
+        Event::key(KEY_LEFTSHIFT, 0),
+        Event::key(KEY_LEFTSHIFT, 1),
+        Event::key(KEY_LEFTSHIFT, 0),
+    ]);
+    events.extend(typing(" ami"));
+    assert_eq!(Screen::read(&translate(&mut translator, &events)).text, "আমিই ami");
+}
+
+#[test]
+fn backspace_erases_the_converted_word() {
+    let output = translate(&mut phonetic(), &typing("kor\x08\x08\x08ami\x08\x08\x08\x08"));
+    assert_eq!(Screen::read(&output).text, "");
+
+    // Past the word, backspace goes to the application
+    let output = translate(&mut phonetic(), &typing("x \x08\x08"));
+    assert_eq!(Screen::read(&output).text, "");
+}
+
+#[test]
+fn shift_is_let_go_while_typing() {
+    let mut translator = phonetic();
+    let mut events = vec![Event::key(KEY_LEFTSHIFT, 1)];
+    events.extend(typing("bha").into_iter().filter(|event| event.code != KEY_LEFTSHIFT));
+    events.push(Event::key(KEY_LEFTSHIFT, 0));
+    // Shift is down for all of "BHA"
+    let output = translate(&mut translator, &events);
+    let screen = Screen::read(&output);
+    assert_eq!(screen.text, BengaliKeyboard::new().convert_text("BHA"));
+    assert!(!screen.shift);
+}
+
+#[test]
+fn shortcuts_pass_through_and_end_the_word() {
+    let mut events = typing("am");
+    events.extend([
+        Event::key(KEY_LEFTCTRL, 1),
+        Event::key(46, 1),
+        Event::key(46, 0),
+        Event::key(KEY_LEFTCTRL, 0),
+    ]);
+    events.extend(typing("i "));
+    let screen = Screen::read(&translate(&mut phonetic(), &events));
+    assert_eq!(screen.text, "আমই ");
+    assert_eq!(screen.shortcuts, ['c']);
+}
+
+#[test]
+fn f10_toggles_conversion() {
+    let mut translator = phonetic();
+    let mut events = vec![Event::key(KEY_F10, 1), Event::key(KEY_F10, 0)];
+    events.extend(typing("ami "));
+    let output = translate(&mut translator, &events);
+    assert!(!translator.is_enabled());
+    assert_eq!(Screen::read(&output).text, "ami ");
+    assert!(!output.iter().any(|event| event.code == KEY_F10));
+}
+
+#[test]
+fn configured_hotkeys_switch_layouts_and_type_english() {
+    let mut translator = phonetic();
+    translator.set_hotkeys(
+        Hotkeys::from_toml("force_english = \"Shift Shift\"\nswitch_layout = \"Ctrl+Alt+F12\"").unwrap(),
+    );
+    let mut events = typing("ami ");
+    events.extend([
+        Event::key(KEY_LEFTSHIFT, 1),
+        Event::key(KEY_LEFTSHIFT, 0),
+        Event::key(KEY_LEFTSHIFT, 1),
+        Event::key(KEY_LEFTSHIFT, 0),
+    ]);
+    events.extend(typing("email ami"));
+    assert_eq!(Screen::read(&translate(&mut translator, &events)).text, "আমি email আমি");
+
+    let output = translate(
+        &mut translator,
+        &[
+            Event::key(KEY_LEFTCTRL, 1),
+            Event::key(56, 1),
+            Event::key(88, 1),
+            Event::key(88, 0),
+            Event::key(56, 0),
+            Event::key(KEY_LEFTCTRL, 0),
+        ],
+    );
+    assert_eq!(translator.layout_name(), "bijoy");
+    assert!(!output.iter().any(|event| event.code == 88), "F12 leaked: {output:?}");
+}
+
+#[test]
+fn right_alt_is_altgr_for_fixed_layouts() {
+    let mut translator = Translator::new(BengaliKeyboard::with_fixed_layout(
+        FixedLayout::builtin("national").unwrap(),
+    ));
+    let mut events = vec![Event::key(KEY_RIGHTALT, 1)];
+    events.extend(typing("f"));
+    events.push(Event::key(KEY_RIGHTALT, 0));
+    let output = translate(&mut translator, &events);
+    assert_eq!(Screen::read(&output).text, "আ");
+}
+
+#[test]
+#[ignore = "needs /dev/uinput"]
+fn round_trip_through_uinput() {
+    let writable = OpenOptions::new().write(true).open("/dev/uinput").is_ok();
+    assert!(writable, "/dev/uinput is not writable");
+
+    // A fake physical keyboard, the daemon's output, and a reader on it
+    let mut source = VirtualKeyboard::create_named("Bengali keyboard test source").unwrap();
+    let mut output = VirtualKeyboard::create().unwrap();
+    // Give udev a moment to create the nodes
+    thread::sleep(Duration::from_millis(500));
+    let mut grabbed = Keyboard::open(source.event_path().unwrap()).unwrap();
+    let mut reader = Keyboard::open(output.event_path().unwrap()).unwrap();
+    assert!(grabbed.has_letter_keys());
+    grabbed.grab().unwrap();
+
+    let daemon = thread::spawn(move || {
+        let mut translator = phonetic();
+        // Ends when the source keyboard is destroyed
+        let _ = translator.run(&mut grabbed, &mut output);
+    });
+
+    let mut typed = Vec::new();
+    for event in typing("ami ") {
+        typed.push(event);
+        typed.push(Event::report());
+    }
+    bengali_keyboard_evdev::EventSink::emit(&mut source, &typed).unwrap();
+
+    let mut screen = Screen::default();
+    while screen.text != "আমি " {
+        let events = reader.read_events().unwrap();
+        assert!(!events.is_empty());
+        for event in events.iter().filter(|event| event.kind == EV_KEY) {
+            screen.key(event.code, event.value);
+        }
+    }
+
+    drop(source);
+    daemon.join().unwrap();
+}
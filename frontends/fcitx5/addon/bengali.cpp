@@ -0,0 +1,226 @@
+// Fcitx5 input method addon driving the Rust engine through bengali_engine.h.
+
+#include "bengali_engine.h"
+
+#include <fcitx-utils/textformatflags.h>
+#include <fcitx/addonfactory.h>
+#include <fcitx/addonmanager.h>
+#include <fcitx/candidatelist.h>
+#include <fcitx/inputcontext.h>
+#include <fcitx/inputcontextmanager.h>
+#include <fcitx/inputcontextproperty.h>
+#include <fcitx/inputmethodengine.h>
+#include <fcitx/inputpanel.h>
+#include <fcitx/instance.h>
+#include <fcitx/surroundingtext.h>
+#include <fcitx/text.h>
+#include <fcitx/userinterface.h>
+
+#include <algorithm>
+#include <memory>
+#include <string>
+
+namespace {
+
+class BengaliState;
+
+class BengaliCandidate : public fcitx::CandidateWord {
+public:
+    BengaliCandidate(BengaliState *state, size_t index, std::string text)
+        : fcitx::CandidateWord(fcitx::Text(std::move(text))), state_(state),
+          index_(index) {}
+
+    void select(fcitx::InputContext *) const override;
+
+private:
+    BengaliState *state_;
+    size_t index_;
+};
+
+// The engine layout for an input method: "bengali" is the phonetic one,
+// "bengali-bijoy" and so on the fixed layouts.
+std::string layoutFor(const fcitx::InputMethodEntry &entry) {
+    const std::string &name = entry.uniqueName();
+    const std::string prefix = "bengali-";
+    if (name.compare(0, prefix.size(), prefix) == 0) {
+        return name.substr(prefix.size());
+    }
+    return "phonetic";
+}
+
+// Per input context state: one Rust engine each, for the layout of the
+// input method last used in this context.
+class BengaliState : public fcitx::InputContextProperty {
+public:
+    explicit BengaliState(fcitx::InputContext *ic)
+        : ic_(ic), layout_("phonetic"), engine_(bengali_engine_new()) {}
+    ~BengaliState() override { bengali_engine_free(engine_); }
+
+    void setLayout(const std::string &layout) {
+        if (layout == layout_) {
+            return;
+        }
+        BengaliEngine *engine = bengali_engine_new_with_layout(layout.c_str());
+        if (!engine) {
+            return;
+        }
+        flush();
+        bengali_engine_free(engine_);
+        engine_ = engine;
+        layout_ = layout;
+    }
+
+    void keyEvent(fcitx::KeyEvent &event) {
+        const fcitx::Key &key = event.rawKey();
+        uint32_t modifiers = 0;
+        if (key.states().test(fcitx::KeyState::Ctrl)) {
+            modifiers |= BENGALI_MOD_CTRL;
+        }
+        if (key.states().test(fcitx::KeyState::Alt)) {
+            modifiers |= BENGALI_MOD_ALT;
+        }
+        if (key.states().test(fcitx::KeyState::Super)) {
+            modifiers |= BENGALI_MOD_SUPER;
+        }
+        if (key.states().test(fcitx::KeyState::Shift)) {
+            modifiers |= BENGALI_MOD_SHIFT;
+        }
+
+        if (bengali_engine_process_key(engine_, key.sym(), modifiers,
+                                       event.isRelease())) {
+            event.filterAndAccept();
+        }
+        update();
+        if (bengali_engine_take_convert_selection(engine_)) {
+            convertSelection();
+        }
+    }
+
+    void selectCandidate(size_t index) {
+        bengali_engine_select_candidate(engine_, index);
+        update();
+    }
+
+    void flush() {
+        bengali_engine_flush(engine_);
+        update();
+    }
+
+    void reset() {
+        bengali_engine_reset(engine_);
+        update();
+    }
+
+private:
+    // Replaces the selection with its conversion, for applications that
+    // report their surrounding text.
+    void convertSelection() {
+        if (!ic_->capabilityFlags().test(
+                fcitx::CapabilityFlag::SurroundingText)) {
+            return;
+        }
+        const fcitx::SurroundingText &surrounding = ic_->surroundingText();
+        if (!surrounding.isValid() ||
+            surrounding.cursor() == surrounding.anchor()) {
+            return;
+        }
+        unsigned int start =
+            std::min(surrounding.cursor(), surrounding.anchor());
+        unsigned int end = std::max(surrounding.cursor(), surrounding.anchor());
+        std::string converted =
+            bengali_engine_convert(engine_, surrounding.selectedText().c_str());
+        ic_->deleteSurroundingText(
+            static_cast<int>(start) - static_cast<int>(surrounding.cursor()),
+            end - start);
+        ic_->commitString(converted);
+    }
+
+    void update() {
+        std::string commit = bengali_engine_take_commit(engine_);
+        if (!commit.empty()) {
+            ic_->commitString(commit);
+        }
+
+        auto &panel = ic_->inputPanel();
+        panel.reset();
+
+        std::string preedit = bengali_engine_preedit(engine_);
+        if (!preedit.empty()) {
+            fcitx::Text text;
+            text.append(preedit, fcitx::TextFormatFlag::Underline);
+            text.setCursor(preedit.size());
+            if (ic_->capabilityFlags().test(fcitx::CapabilityFlag::Preedit)) {
+                panel.setClientPreedit(text);
+            } else {
+                panel.setPreedit(text);
+            }
+
+            auto candidates = std::make_unique<fcitx::CommonCandidateList>();
+            size_t count = bengali_engine_candidate_count(engine_);
+            for (size_t i = 0; i < count; i++) {
+                candidates->append<BengaliCandidate>(
+                    this, i, bengali_engine_candidate(engine_, i));
+            }
+            if (count > 1) {
+                panel.setCandidateList(std::move(candidates));
+            }
+        }
+
+        ic_->updatePreedit();
+        ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+    }
+
+    fcitx::InputContext *ic_;
+    std::string layout_;
+    BengaliEngine *engine_;
+};
+
+void BengaliCandidate::select(fcitx::InputContext *) const {
+    state_->selectCandidate(index_);
+}
+
+class BengaliIM final : public fcitx::InputMethodEngineV2 {
+public:
+    explicit BengaliIM(fcitx::Instance *instance)
+        : factory_([](fcitx::InputContext &ic) {
+              return new BengaliState(&ic);
+          }) {
+        instance->inputContextManager().registerProperty("bengaliState",
+                                                         &factory_);
+    }
+
+    void keyEvent(const fcitx::InputMethodEntry &entry,
+                  fcitx::KeyEvent &event) override {
+        auto *state = this->state(event.inputContext());
+        state->setLayout(layoutFor(entry));
+        state->keyEvent(event);
+    }
+
+    void reset(const fcitx::InputMethodEntry &,
+               fcitx::InputContextEvent &event) override {
+        state(event.inputContext())->reset();
+    }
+
+    void deactivate(const fcitx::InputMethodEntry &,
+                    fcitx::InputContextEvent &event) override {
+        state(event.inputContext())->flush();
+    }
+
+private:
+    BengaliState *state(fcitx::InputContext *ic) {
+        return ic->propertyFor(&factory_);
+    }
+
+    fcitx::FactoryFor<BengaliState> factory_;
+};
+
+class BengaliIMFactory : public fcitx::AddonFactory {
+public:
+    fcitx::AddonInstance *create(fcitx::AddonManager *manager) override {
+        return new BengaliIM(manager->instance());
+    }
+};
+
+} // namespace
+
+FCITX_ADDON_FACTORY(BengaliIMFactory);
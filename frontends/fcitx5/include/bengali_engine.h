@@ -0,0 +1,53 @@
+/* C API of the Bengali phonetic engine, implemented in Rust
+ * (frontends/fcitx5/src/lib.rs). Returned strings are UTF-8 and stay valid
+ * until the next call on the same engine. */
+#ifndef BENGALI_ENGINE_H
+#define BENGALI_ENGINE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BENGALI_MOD_CTRL (1u << 0)
+#define BENGALI_MOD_ALT (1u << 1)
+#define BENGALI_MOD_SUPER (1u << 2)
+#define BENGALI_MOD_SHIFT (1u << 3)
+
+typedef struct BengaliEngine BengaliEngine;
+
+BengaliEngine *bengali_engine_new(void);
+/* "phonetic", "bijoy", "national" or "probhat"; NULL if unknown. */
+BengaliEngine *bengali_engine_new_with_layout(const char *name);
+void bengali_engine_free(BengaliEngine *engine);
+
+/* Handles an X11 keysym, returns whether the key was consumed. Releases
+ * matter too: tapping a modifier can be a hotkey. */
+bool bengali_engine_process_key(BengaliEngine *engine, uint32_t keysym,
+                                uint32_t modifiers, bool release);
+
+/* Whether the convert selection hotkey was pressed since the last call. */
+bool bengali_engine_take_convert_selection(BengaliEngine *engine);
+const char *bengali_engine_convert(BengaliEngine *engine, const char *text);
+
+const char *bengali_engine_take_commit(BengaliEngine *engine);
+const char *bengali_engine_preedit(BengaliEngine *engine);
+const char *bengali_engine_buffer(BengaliEngine *engine);
+
+size_t bengali_engine_candidate_count(BengaliEngine *engine);
+const char *bengali_engine_candidate(BengaliEngine *engine, size_t index);
+void bengali_engine_select_candidate(BengaliEngine *engine, size_t index);
+
+void bengali_engine_flush(BengaliEngine *engine);
+void bengali_engine_reset(BengaliEngine *engine);
+bool bengali_engine_is_enabled(BengaliEngine *engine);
+void bengali_engine_set_enabled(BengaliEngine *engine, bool enabled);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,429 @@
+//! C API over the transliteration engine for the Fcitx5 addon.
+//!
+//! Each Fcitx5 input context owns one [`BengaliEngine`], for the layout of
+//! the input method it has selected. The addon feeds it
+//! key events and then reads back what to commit and what to show as
+//! preedit and candidates. State follows [`KeyboardState`]: an enabled flag
+//! toggled by a hotkey (F10 unless hotkeys.toml says otherwise) and the
+//! roman buffer of the word in progress. The selection lives on the Fcitx5
+//! side, so for the convert selection hotkey the engine only raises a flag
+//! for the addon to act on.
+//!
+//! Candidates are ranked against the user's dictionary.txt or the bundled
+//! word list, with the spellings the user picked before first. Both are
+//! loaded once for all engines, and a pick is saved to the history at once.
+//!
+//! Strings returned by the API stay valid until the next call on the same
+//! engine.
+
+use bengali_keyboard::hotkey::{self, Key};
+use bengali_keyboard::keysym::{is_modifier_keysym, keysym_to_char, KEY_ESCAPE};
+use bengali_keyboard::{
+    config, BengaliKeyboard, Dictionary, History, HotkeyAction, Hotkeys, InputSink, KeyboardState, Strategy,
+};
+use std::ffi::{c_char, CStr, CString};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Instant;
+
+/// Modifier bits for [`bengali_engine_process_key`], see the C header.
+pub const BENGALI_MOD_CTRL: u32 = 1 << 0;
+pub const BENGALI_MOD_ALT: u32 = 1 << 1;
+pub const BENGALI_MOD_SUPER: u32 = 1 << 2;
+pub const BENGALI_MOD_SHIFT: u32 = 1 << 3;
+
+fn dictionary() -> &'static Dictionary {
+    static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        config::load_dictionary().unwrap_or_else(|e| {
+            eprintln!("fcitx5-bengali: {e}, using the bundled dictionary");
+            Dictionary::builtin()
+        })
+    })
+}
+
+fn hotkeys() -> &'static Hotkeys {
+    static HOTKEYS: OnceLock<Hotkeys> = OnceLock::new();
+    HOTKEYS.get_or_init(|| {
+        config::load_hotkeys().unwrap_or_else(|e| {
+            eprintln!("fcitx5-bengali: {e}, using the default hotkeys");
+            Hotkeys::default()
+        })
+    })
+}
+
+fn history() -> MutexGuard<'static, History> {
+    static HISTORY: OnceLock<Mutex<History>> = OnceLock::new();
+    HISTORY
+        .get_or_init(|| {
+            Mutex::new(config::load_history().unwrap_or_else(|e| {
+                eprintln!("fcitx5-bengali: {e}, starting a new history");
+                History::new()
+            }))
+        })
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub struct BengaliEngine {
+    keyboard: BengaliKeyboard,
+    /// The layout's name, for the switch-layout hotkey to move on from.
+    layout: String,
+    state: KeyboardState,
+    hotkeys: Hotkeys,
+    /// The convert selection hotkey was pressed and the addon has not yet
+    /// asked about it.
+    convert_selection: bool,
+    /// Spellings offered for the current word, best first.
+    candidates: Vec<String>,
+    /// Text finished since the addon last called `take_commit`.
+    pending_commit: String,
+    /// Scratch storage backing the returned C strings.
+    returned: CString,
+}
+
+impl BengaliEngine {
+    fn new(keyboard: BengaliKeyboard, layout: &str) -> Self {
+        let mut state = KeyboardState::new();
+        // Fcitx5 switches input methods itself, so we start out converting
+        state.enabled = true;
+        state.strategy = Strategy::Preedit;
+        Self {
+            keyboard,
+            layout: layout.to_string(),
+            state,
+            hotkeys: hotkeys().clone(),
+            convert_selection: false,
+            candidates: Vec::new(),
+            pending_commit: String::new(),
+            returned: CString::default(),
+        }
+    }
+
+    /// Ranks the spellings for the word in progress.
+    fn update_candidates(&mut self) {
+        let input = &self.state.input_buffer;
+        if input.is_empty() {
+            self.candidates.clear();
+            return;
+        }
+        self.candidates = self.keyboard.candidates(input, dictionary());
+        history().rank(&self.keyboard, input, &mut self.candidates);
+    }
+
+    fn reset_word(&mut self) {
+        self.state.reset_word();
+        self.candidates.clear();
+    }
+
+    /// Moves the converted word to the pending commit.
+    fn finish_word(&mut self) {
+        let mut commit = Commit(&mut self.pending_commit);
+        self.state.finish_word(&self.keyboard, &history(), &mut commit);
+        self.candidates.clear();
+    }
+
+    /// Does what a hotkey is bound to.
+    fn hotkey(&mut self, action: HotkeyAction) {
+        match action {
+            HotkeyAction::Toggle => {
+                self.finish_word();
+                self.state.toggle();
+            }
+            HotkeyAction::ConvertSelection => {
+                self.finish_word();
+                self.convert_selection = true;
+            }
+            HotkeyAction::ForceEnglish => {
+                let mut commit = Commit(&mut self.pending_commit);
+                self.state.force_english_word(&self.keyboard, &history(), &mut commit);
+                self.candidates.clear();
+            }
+            HotkeyAction::SwitchLayout => {
+                let next = config::next_layout(&self.layout);
+                match config::load_keyboard(next) {
+                    Ok(keyboard) => {
+                        self.finish_word();
+                        self.keyboard = keyboard;
+                        self.layout = next.to_string();
+                    }
+                    Err(e) => eprintln!("fcitx5-bengali: {e}"),
+                }
+            }
+        }
+    }
+
+    /// Returns whether the key was consumed.
+    fn process_key(&mut self, keysym: u32, modifiers: u32, release: bool) -> bool {
+        if let Some(key) = Key::from_keysym(keysym) {
+            let held = [
+                (BENGALI_MOD_CTRL, hotkey::CTRL),
+                (BENGALI_MOD_ALT, hotkey::ALT),
+                (BENGALI_MOD_SHIFT, hotkey::SHIFT),
+                (BENGALI_MOD_SUPER, hotkey::SUPER),
+            ]
+            .into_iter()
+            .filter(|&(mask, _)| modifiers & mask != 0)
+            .fold(0, |held, (_, bit)| held | bit);
+            if let Some(action) = self.hotkeys.key(key, held, !release, Instant::now()) {
+                self.hotkey(action);
+                // A tapped modifier's press went through, so its release does
+                return !release;
+            }
+        }
+        if release {
+            return false;
+        }
+
+        if !self.state.enabled {
+            return false;
+        }
+
+        if is_modifier_keysym(keysym) {
+            // Shift and AltGr change the next key; the word goes on
+            return false;
+        }
+
+        if modifiers & (BENGALI_MOD_CTRL | BENGALI_MOD_ALT | BENGALI_MOD_SUPER) != 0 {
+            // Shortcuts go to the application, with the word finished first
+            self.finish_word();
+            return false;
+        }
+
+        let mut commit = Commit(&mut self.pending_commit);
+        let consumed = match (keysym, keysym_to_char(keysym)) {
+            (KEY_ESCAPE, _) => self.state.cancel_word(&mut commit),
+            (_, Some(ch)) => self.state.process_key(&self.keyboard, &history(), ch, false, &mut commit),
+            _ => {
+                self.state.finish_word(&self.keyboard, &history(), &mut commit);
+                false
+            }
+        };
+        self.update_candidates();
+        consumed
+    }
+
+    fn select_candidate(&mut self, index: usize) {
+        let Some(candidate) = self.candidates.get(index) else {
+            return;
+        };
+        self.pending_commit.push_str(candidate);
+        if self.keyboard.keymap().is_none() {
+            self.reset_word();
+            return;
+        }
+
+        let mut history = history();
+        history.record(&self.state.input_buffer, candidate);
+        if let Some(path) = config::history_path() {
+            if let Err(e) = history.save(path) {
+                eprintln!("fcitx5-bengali: {e}");
+            }
+        }
+        drop(history);
+        self.reset_word();
+    }
+
+    fn return_str(&mut self, text: String) -> *const c_char {
+        // Roman input and Bengali output never contain NUL
+        self.returned = CString::new(text).unwrap_or_default();
+        self.returned.as_ptr()
+    }
+}
+
+/// Appends what [`KeyboardState`] commits to the pending commit; the addon
+/// reads the preedit from the state itself.
+struct Commit<'a>(&'a mut String);
+
+impl InputSink for Commit<'_> {
+    fn send_backspaces(&mut self, _count: usize) {}
+
+    fn send_text(&mut self, text: &str) {
+        self.0.push_str(text);
+    }
+}
+
+/// Runs `f` on the engine behind `engine`.
+///
+/// # Safety
+/// `engine` must come from [`bengali_engine_new`] and not have been freed.
+unsafe fn with<R>(engine: *mut BengaliEngine, f: impl FnOnce(&mut BengaliEngine) -> R) -> R {
+    f(&mut *engine)
+}
+
+/// An engine for the phonetic layout: the user's layout.toml, falling back
+/// to the built-in layout with a message in the Fcitx5 log.
+#[no_mangle]
+pub extern "C" fn bengali_engine_new() -> *mut BengaliEngine {
+    let keyboard = config::load_keyboard("phonetic").unwrap_or_else(|e| {
+        eprintln!("fcitx5-bengali: {e}, using the built-in layout");
+        BengaliKeyboard::new()
+    });
+    Box::into_raw(Box::new(BengaliEngine::new(keyboard, "phonetic")))
+}
+
+/// An engine for a layout by name: `phonetic`, `bijoy`, `national` or
+/// `probhat`. Returns NULL for an unknown name.
+///
+/// # Safety
+/// `name` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_new_with_layout(name: *const c_char) -> *mut BengaliEngine {
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return std::ptr::null_mut();
+    };
+    if name == "phonetic" {
+        return bengali_engine_new();
+    }
+    match config::load_keyboard(name) {
+        Ok(keyboard) => Box::into_raw(Box::new(BengaliEngine::new(keyboard, name))),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `engine` must come from [`bengali_engine_new`] and is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_free(engine: *mut BengaliEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Handles one key event, returning whether it was consumed.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_process_key(
+    engine: *mut BengaliEngine,
+    keysym: u32,
+    modifiers: u32,
+    release: bool,
+) -> bool {
+    with(engine, |engine| engine.process_key(keysym, modifiers, release))
+}
+
+/// Returns and clears whether the convert selection hotkey was pressed; the
+/// addon then replaces the selection with [`bengali_engine_convert`].
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_take_convert_selection(engine: *mut BengaliEngine) -> bool {
+    with(engine, |engine| std::mem::take(&mut engine.convert_selection))
+}
+
+/// `text` converted with the engine's layout.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`] and `text` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_convert(engine: *mut BengaliEngine, text: *const c_char) -> *const c_char {
+    let text = CStr::from_ptr(text).to_string_lossy();
+    with(engine, |engine| {
+        let converted = engine.keyboard.convert_text(&text);
+        engine.return_str(converted)
+    })
+}
+
+/// Returns and clears the text to commit, empty if there is none.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_take_commit(engine: *mut BengaliEngine) -> *const c_char {
+    with(engine, |engine| {
+        let commit = std::mem::take(&mut engine.pending_commit);
+        engine.return_str(commit)
+    })
+}
+
+/// The converted word in progress.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_preedit(engine: *mut BengaliEngine) -> *const c_char {
+    with(engine, |engine| {
+        let preedit = engine.state.last_bengali_output.clone();
+        engine.return_str(preedit)
+    })
+}
+
+/// The roman keys typed for the word in progress.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_buffer(engine: *mut BengaliEngine) -> *const c_char {
+    with(engine, |engine| {
+        let buffer = engine.state.input_buffer.clone();
+        engine.return_str(buffer)
+    })
+}
+
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_candidate_count(engine: *mut BengaliEngine) -> usize {
+    with(engine, |engine| engine.candidates.len())
+}
+
+/// The candidate at `index`, or an empty string if out of range.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_candidate(engine: *mut BengaliEngine, index: usize) -> *const c_char {
+    with(engine, |engine| {
+        let candidate = engine.candidates.get(index).cloned().unwrap_or_default();
+        engine.return_str(candidate)
+    })
+}
+
+/// Commits the candidate at `index` in place of the word in progress.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_select_candidate(engine: *mut BengaliEngine, index: usize) {
+    with(engine, |engine| engine.select_candidate(index))
+}
+
+/// Moves the word in progress to the pending commit, e.g. on focus out.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_flush(engine: *mut BengaliEngine) {
+    with(engine, |engine| engine.finish_word())
+}
+
+/// Drops the word in progress without committing it.
+///
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_reset(engine: *mut BengaliEngine) {
+    with(engine, |engine| engine.reset_word())
+}
+
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_is_enabled(engine: *mut BengaliEngine) -> bool {
+    with(engine, |engine| engine.state.enabled)
+}
+
+/// # Safety
+/// `engine` must be a live engine from [`bengali_engine_new`].
+#[no_mangle]
+pub unsafe extern "C" fn bengali_engine_set_enabled(engine: *mut BengaliEngine, enabled: bool) {
+    with(engine, |engine| {
+        if engine.state.enabled != enabled {
+            engine.finish_word();
+            engine.state.toggle();
+        }
+    })
+}
@@ -0,0 +1,178 @@
+//! Exercises the C API the way the Fcitx5 addon calls it.
+
+use bengali_keyboard::keysym::{KEY_BACKSPACE, KEY_F10};
+use bengali_keyboard_fcitx5::*;
+use std::ffi::{c_char, CStr};
+use std::sync::Once;
+
+/// A config directory of the tests' own, so the user's history and
+/// dictionary are neither used nor changed.
+fn test_config_dir() -> std::path::PathBuf {
+    static CONFIG: Once = Once::new();
+    let dir = std::env::temp_dir().join(format!("fcitx5-bengali-test-{}", std::process::id()));
+    CONFIG.call_once(|| std::env::set_var("XDG_CONFIG_HOME", &dir));
+    dir
+}
+
+fn new_engine() -> *mut BengaliEngine {
+    test_config_dir();
+    bengali_engine_new()
+}
+
+fn string(ptr: *const c_char) -> String {
+    unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string()
+}
+
+fn type_keys(engine: *mut BengaliEngine, keys: &str) -> String {
+    let mut committed = String::new();
+    for ch in keys.chars() {
+        unsafe {
+            bengali_engine_process_key(engine, ch as u32, 0, false);
+            committed.push_str(&string(bengali_engine_take_commit(engine)));
+        }
+    }
+    committed
+}
+
+#[test]
+fn preedit_then_commit_on_space() {
+    let engine = new_engine();
+    unsafe {
+        assert_eq!(type_keys(engine, "ama"), "");
+        assert_eq!(string(bengali_engine_preedit(engine)), "আমা");
+        assert_eq!(string(bengali_engine_buffer(engine)), "ama");
+
+        assert_eq!(type_keys(engine, "r "), "আমার ");
+        assert_eq!(string(bengali_engine_preedit(engine)), "");
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn finished_words_are_autocorrected() {
+    let engine = new_engine();
+    unsafe {
+        type_keys(engine, "valo");
+        assert_eq!(string(bengali_engine_preedit(engine)), "ভাল");
+        assert_eq!(type_keys(engine, " "), "ভালো ");
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn backspace_edits_the_preedit() {
+    let engine = new_engine();
+    unsafe {
+        type_keys(engine, "amarr");
+        assert!(bengali_engine_process_key(engine, KEY_BACKSPACE, 0, false));
+        assert_eq!(string(bengali_engine_preedit(engine)), "আমার");
+        // With no word in progress the application gets the backspace
+        type_keys(engine, " ");
+        assert!(!bengali_engine_process_key(engine, KEY_BACKSPACE, 0, false));
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn toggle_and_enabled_flag() {
+    let engine = new_engine();
+    unsafe {
+        assert!(bengali_engine_is_enabled(engine));
+        type_keys(engine, "ami");
+        assert!(bengali_engine_process_key(engine, KEY_F10, 0, false));
+        assert!(!bengali_engine_is_enabled(engine));
+        // Toggling off commits the word in progress
+        assert_eq!(string(bengali_engine_take_commit(engine)), "আমি");
+        assert!(!bengali_engine_process_key(engine, 'a' as u32, 0, false));
+
+        bengali_engine_set_enabled(engine, true);
+        assert!(bengali_engine_is_enabled(engine));
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn candidates_and_selection() {
+    let engine = new_engine();
+    unsafe {
+        assert_eq!(bengali_engine_candidate_count(engine), 0);
+        type_keys(engine, "bangla");
+        assert_eq!(bengali_engine_candidate_count(engine), 1);
+        assert_eq!(string(bengali_engine_candidate(engine, 0)), "বাংলা");
+
+        bengali_engine_select_candidate(engine, 0);
+        assert_eq!(string(bengali_engine_take_commit(engine)), "বাংলা");
+        assert_eq!(string(bengali_engine_buffer(engine)), "");
+        assert_eq!(bengali_engine_candidate_count(engine), 0);
+
+        // The dictionary word first, then the spelling as typed
+        type_keys(engine, "shokal");
+        assert_eq!(bengali_engine_candidate_count(engine), 2);
+        assert_eq!(string(bengali_engine_candidate(engine, 0)), "সকাল");
+        bengali_engine_select_candidate(engine, 1);
+        assert_eq!(string(bengali_engine_take_commit(engine)), "শকাল");
+
+        // The pick is remembered, for the preedit, the commit and candidates
+        type_keys(engine, "shokal");
+        assert_eq!(string(bengali_engine_preedit(engine)), "শকাল");
+        assert_eq!(string(bengali_engine_candidate(engine, 0)), "শকাল");
+        assert_eq!(type_keys(engine, " "), "শকাল ");
+        bengali_engine_free(engine);
+        std::fs::remove_dir_all(test_config_dir()).unwrap();
+    }
+}
+
+#[test]
+fn modifier_keys_leave_the_word_going() {
+    const SHIFT_L: u32 = 0xffe1;
+    let engine = new_engine();
+    unsafe {
+        type_keys(engine, "k");
+        assert!(!bengali_engine_process_key(engine, SHIFT_L, 0, false));
+        assert!(bengali_engine_process_key(engine, 'S' as u32, BENGALI_MOD_SHIFT, false));
+        assert!(!bengali_engine_process_key(engine, SHIFT_L, BENGALI_MOD_SHIFT, true));
+        assert_eq!(type_keys(engine, "h"), "");
+        assert_eq!(string(bengali_engine_preedit(engine)), "ক্ষ");
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn shortcuts_pass_through_after_committing() {
+    let engine = new_engine();
+    unsafe {
+        type_keys(engine, "ami");
+        assert!(!bengali_engine_process_key(engine, 'c' as u32, BENGALI_MOD_CTRL, false));
+        assert_eq!(string(bengali_engine_take_commit(engine)), "আমি");
+        bengali_engine_free(engine);
+    }
+}
+
+#[test]
+fn fixed_layout_engine() {
+    test_config_dir();
+    unsafe {
+        let engine = bengali_engine_new_with_layout(c"bijoy".as_ptr());
+        assert!(!engine.is_null());
+        // ে is typed before the consonant and the link key g makes vowels
+        assert_eq!(type_keys(engine, "cj"), "");
+        assert_eq!(string(bengali_engine_preedit(engine)), "কে");
+        assert_eq!(type_keys(engine, " gf "), "কে আ ");
+        bengali_engine_free(engine);
+
+        assert!(bengali_engine_new_with_layout(c"dvorak".as_ptr()).is_null());
+    }
+}
+
+#[test]
+fn selection_is_converted_with_the_engines_layout() {
+    test_config_dir();
+    unsafe {
+        let engine = bengali_engine_new_with_layout(c"bijoy".as_ptr());
+        assert_eq!(string(bengali_engine_convert(engine, c"cj gf".as_ptr())), "কে আ");
+        // Only the hotkey asks the addon to convert the selection
+        type_keys(engine, "cj ");
+        assert!(!bengali_engine_take_convert_selection(engine));
+        bengali_engine_free(engine);
+    }
+}
@@ -0,0 +1,344 @@
+//! The `org.freedesktop.IBus.Engine` object and the factory that creates it.
+
+use crate::text;
+use bengali_keyboard::hotkey::{self, Key};
+use bengali_keyboard::keysym::{is_modifier_keysym, keysym_to_char, KEY_ESCAPE};
+use bengali_keyboard::{config, BengaliKeyboard, History, HotkeyAction, Hotkeys, InputSink, KeyboardState, Strategy};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use zbus::object_server::{ObjectServer, SignalEmitter};
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::{fdo, interface};
+
+/// Engines are named `bengali-` followed by one of [`config::layout_names`].
+pub const ENGINE_PREFIX: &str = "bengali-";
+
+// Modifier masks from ibustypes.h
+const SHIFT_MASK: u32 = 1 << 0;
+const CONTROL_MASK: u32 = 1 << 2;
+const MOD1_MASK: u32 = 1 << 3;
+const MOD4_MASK: u32 = 1 << 6;
+const SUPER_MASK: u32 = 1 << 26;
+const RELEASE_MASK: u32 = 1 << 30;
+
+/// Preedit modes for `UpdatePreeditText`.
+const PREEDIT_COMMIT: u32 = 1;
+
+/// The keyboard for a layout, falling back to the built-in phonetic layout
+/// if the user's layout.toml is broken.
+fn load_keyboard(layout: &str) -> BengaliKeyboard {
+    config::load_keyboard(layout).unwrap_or_else(|e| {
+        eprintln!("ibus-engine-bengali-phonetic: {e}, using the built-in layout");
+        BengaliKeyboard::new()
+    })
+}
+
+/// Creates one [`Engine`] per input context that selects us.
+pub struct Factory {
+    /// Keyboards by layout name, loaded when first asked for.
+    keyboards: HashMap<String, Arc<BengaliKeyboard>>,
+    /// Spellings the user picked in a front-end with candidates.
+    history: Arc<History>,
+    hotkeys: Hotkeys,
+    next_id: u32,
+}
+
+impl Factory {
+    pub fn new() -> Self {
+        let history = config::load_history().unwrap_or_else(|e| {
+            eprintln!("ibus-engine-bengali-phonetic: {e}, ignoring the history");
+            History::new()
+        });
+        let hotkeys = config::load_hotkeys().unwrap_or_else(|e| {
+            eprintln!("ibus-engine-bengali-phonetic: {e}, using the default hotkeys");
+            Hotkeys::default()
+        });
+        Self { keyboards: HashMap::new(), history: Arc::new(history), hotkeys, next_id: 1 }
+    }
+}
+
+#[interface(name = "org.freedesktop.IBus.Factory")]
+impl Factory {
+    async fn create_engine(
+        &mut self,
+        #[zbus(object_server)] server: &ObjectServer,
+        name: &str,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let layout = name
+            .strip_prefix(ENGINE_PREFIX)
+            .filter(|layout| config::layout_names().any(|known| known == *layout))
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("unknown engine {name}")))?;
+        let keyboard = self
+            .keyboards
+            .entry(layout.to_string())
+            .or_insert_with(|| Arc::new(load_keyboard(layout)))
+            .clone();
+
+        let path = format!("/org/freedesktop/IBus/Engine/{}", self.next_id);
+        self.next_id += 1;
+
+        let path = OwnedObjectPath::try_from(path).map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let engine = Engine::new(keyboard, layout, self.history.clone(), self.hotkeys.clone());
+        server.at(&path, engine).await?;
+        server.at(&path, Service).await?;
+        Ok(path)
+    }
+}
+
+/// Lets IBus tear an engine object down.
+pub struct Service;
+
+#[interface(name = "org.freedesktop.IBus.Service")]
+impl Service {
+    async fn destroy(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(header)] header: zbus::message::Header<'_>,
+    ) -> fdo::Result<()> {
+        if let Some(path) = header.path() {
+            server.remove::<Engine, _>(path).await?;
+            server.remove::<Service, _>(path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// One input context's engine: the current word is shown as an underlined
+/// preedit and committed on a word boundary, so nothing is ever typed and
+/// erased in the application.
+pub struct Engine {
+    keyboard: Arc<BengaliKeyboard>,
+    /// The layout's name, for the switch-layout hotkey to move on from.
+    layout: String,
+    history: Arc<History>,
+    state: KeyboardState,
+    hotkeys: Hotkeys,
+    /// The text around the cursor, with the cursor and the selection's
+    /// anchor in characters, as the application last reported them.
+    surrounding: Option<(String, u32, u32)>,
+}
+
+impl Engine {
+    fn new(keyboard: Arc<BengaliKeyboard>, layout: &str, history: Arc<History>, hotkeys: Hotkeys) -> Self {
+        let mut state = KeyboardState::new();
+        // IBus switches engines itself, so we start out converting
+        state.enabled = true;
+        state.strategy = Strategy::Preedit;
+        Self { keyboard, layout: layout.to_string(), history, state, hotkeys, surrounding: None }
+    }
+
+    /// Sends what [`KeyboardState`] wrote, in order.
+    async fn emit(emitter: &SignalEmitter<'_>, output: Output) -> zbus::Result<()> {
+        for signal in output.0 {
+            match signal {
+                Signal::Preedit(preedit) => {
+                    let cursor = preedit.chars().count() as u32;
+                    let visible = !preedit.is_empty();
+                    Self::update_preedit_text(emitter, text::underlined(&preedit), cursor, visible, PREEDIT_COMMIT)
+                        .await?
+                }
+                Signal::Commit(text) => Self::commit_text(emitter, text::plain(&text)).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Commits the converted word and clears the preedit.
+    async fn finish_word(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        let mut output = Output::default();
+        self.state.finish_word(&self.keyboard, &self.history, &mut output);
+        Self::emit(emitter, output).await
+    }
+
+    /// Replaces the selection with its conversion.
+    async fn convert_selection(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        self.finish_word(emitter).await?;
+        let Some((text, cursor, anchor)) = self.surrounding.take() else {
+            return Ok(());
+        };
+        let (start, end) = (cursor.min(anchor), cursor.max(anchor));
+        if start == end {
+            return Ok(());
+        }
+        let selected: String = text.chars().skip(start as usize).take((end - start) as usize).collect();
+        let converted = self.keyboard.convert_text(&selected);
+        Self::delete_surrounding_text(emitter, start as i32 - cursor as i32, end - start).await?;
+        Self::commit_text(emitter, text::plain(&converted)).await
+    }
+
+    /// Does what a hotkey is bound to.
+    async fn hotkey(&mut self, emitter: &SignalEmitter<'_>, action: HotkeyAction) -> zbus::Result<()> {
+        match action {
+            HotkeyAction::Toggle => {
+                self.finish_word(emitter).await?;
+                self.state.toggle();
+            }
+            HotkeyAction::ConvertSelection => self.convert_selection(emitter).await?,
+            HotkeyAction::ForceEnglish => {
+                let mut output = Output::default();
+                self.state.force_english_word(&self.keyboard, &self.history, &mut output);
+                Self::emit(emitter, output).await?;
+            }
+            HotkeyAction::SwitchLayout => {
+                let next = config::next_layout(&self.layout);
+                match config::load_keyboard(next) {
+                    Ok(keyboard) => {
+                        self.finish_word(emitter).await?;
+                        self.keyboard = Arc::new(keyboard);
+                        self.layout = next.to_string();
+                    }
+                    Err(e) => eprintln!("ibus-engine-bengali-phonetic: {e}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_key(&mut self, emitter: &SignalEmitter<'_>, keyval: u32, modifiers: u32) -> zbus::Result<bool> {
+        let press = modifiers & RELEASE_MASK == 0;
+        if let Some(key) = Key::from_keysym(keyval) {
+            let held = [
+                (CONTROL_MASK, hotkey::CTRL),
+                (MOD1_MASK, hotkey::ALT),
+                (SHIFT_MASK, hotkey::SHIFT),
+                (MOD4_MASK | SUPER_MASK, hotkey::SUPER),
+            ]
+            .into_iter()
+            .filter(|&(mask, _)| modifiers & mask != 0)
+            .fold(0, |held, (_, bit)| held | bit);
+            if let Some(action) = self.hotkeys.key(key, held, press, Instant::now()) {
+                self.hotkey(emitter, action).await?;
+                // A tapped modifier's press went through, so its release does
+                return Ok(press);
+            }
+        }
+        if !press {
+            return Ok(false);
+        }
+
+        if !self.state.enabled {
+            return Ok(false);
+        }
+
+        if is_modifier_keysym(keyval) {
+            // Shift and AltGr change the next key; the word goes on
+            return Ok(false);
+        }
+
+        if modifiers & (CONTROL_MASK | MOD1_MASK | SUPER_MASK) != 0 {
+            // Shortcuts go to the application, with the word finished first
+            self.finish_word(emitter).await?;
+            return Ok(false);
+        }
+
+        let mut output = Output::default();
+        let consumed = match (keyval, keysym_to_char(keyval)) {
+            (KEY_ESCAPE, _) => self.state.cancel_word(&mut output),
+            (_, Some(ch)) => self.state.process_key(&self.keyboard, &self.history, ch, false, &mut output),
+            _ => {
+                self.state.finish_word(&self.keyboard, &self.history, &mut output);
+                false
+            }
+        };
+        Self::emit(emitter, output).await?;
+        Ok(consumed)
+    }
+}
+
+enum Signal {
+    Preedit(String),
+    Commit(String),
+}
+
+/// Collects what [`KeyboardState`] writes, to be sent as signals once it
+/// is done.
+#[derive(Default)]
+struct Output(Vec<Signal>);
+
+impl InputSink for Output {
+    fn send_backspaces(&mut self, _count: usize) {}
+
+    fn send_text(&mut self, text: &str) {
+        self.0.push(Signal::Commit(text.to_string()));
+    }
+
+    fn update_preedit(&mut self, text: &str) {
+        self.0.push(Signal::Preedit(text.to_string()));
+    }
+}
+
+#[interface(name = "org.freedesktop.IBus.Engine")]
+impl Engine {
+    async fn process_key_event(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        keyval: u32,
+        _keycode: u32,
+        state: u32,
+    ) -> fdo::Result<bool> {
+        Ok(self.handle_key(&emitter, keyval, state).await?)
+    }
+
+    async fn focus_in(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        // The application sends its text from now on, for convert selection
+        Ok(Self::require_surrounding_text(&emitter).await?)
+    }
+
+    async fn focus_out(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        self.hotkeys.reset();
+        self.surrounding = None;
+        Ok(self.finish_word(&emitter).await?)
+    }
+
+    async fn set_surrounding_text(&mut self, text: Value<'_>, cursor_pos: u32, anchor_pos: u32) {
+        self.surrounding = text::read(&text).map(|text| (text, cursor_pos, anchor_pos));
+    }
+
+    async fn reset(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        self.state.reset_word();
+        Ok(Self::update_preedit_text(&emitter, text::plain(""), 0, false, PREEDIT_COMMIT).await?)
+    }
+
+    async fn enable(&mut self) {
+        self.state.enabled = true;
+    }
+
+    async fn disable(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        self.finish_word(&emitter).await?;
+        self.state.enabled = false;
+        Ok(())
+    }
+
+    // Calls IBus makes on every engine that we have no use for.
+    async fn focus_in_id(&self, _object_path: &str, _client: &str) {}
+    async fn focus_out_id(&self, _object_path: &str) {}
+    async fn set_cursor_location(&self, _x: i32, _y: i32, _w: i32, _h: i32) {}
+    async fn set_capabilities(&self, _caps: u32) {}
+    async fn property_activate(&self, _name: &str, _state: u32) {}
+    async fn property_show(&self, _name: &str) {}
+    async fn property_hide(&self, _name: &str) {}
+    async fn candidate_clicked(&self, _index: u32, _button: u32, _state: u32) {}
+    async fn page_up(&self) {}
+    async fn page_down(&self) {}
+    async fn cursor_up(&self) {}
+    async fn cursor_down(&self) {}
+
+    #[zbus(signal)]
+    async fn commit_text(emitter: &SignalEmitter<'_>, text: Value<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn delete_surrounding_text(emitter: &SignalEmitter<'_>, offset: i32, nchars: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn require_surrounding_text(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn update_preedit_text(
+        emitter: &SignalEmitter<'_>,
+        text: Value<'_>,
+        cursor_pos: u32,
+        visible: bool,
+        mode: u32,
+    ) -> zbus::Result<()>;
+}
@@ -0,0 +1,69 @@
+//! IBus engine for the Bengali phonetic keyboard.
+//!
+//! ibus-daemon starts this binary (see `bengali-phonetic.xml`) and asks the
+//! factory it exports for an engine whenever an input context selects
+//! `bengali-phonetic` or one of the fixed layouts, `bengali-bijoy`,
+//! `bengali-national` and `bengali-probhat`.
+
+mod engine;
+mod text;
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use zbus::blocking::connection;
+
+const BUS_NAME: &str = "org.freedesktop.IBus.BengaliPhonetic";
+const FACTORY_PATH: &str = "/org/freedesktop/IBus/Factory";
+
+/// Finds the address of the running ibus-daemon the same way libibus does:
+/// `$IBUS_ADDRESS`, or the address file written for this machine and display.
+fn ibus_address() -> Result<String, Box<dyn Error>> {
+    if let Ok(address) = env::var("IBUS_ADDRESS") {
+        return Ok(address);
+    }
+
+    let machine_id = fs::read_to_string("/etc/machine-id")
+        .or_else(|_| fs::read_to_string("/var/lib/dbus/machine-id"))?;
+
+    // DISPLAY is "host:number.screen"; without X11 libibus uses the Wayland
+    // socket name as the display number
+    let (host, number) = match env::var("DISPLAY") {
+        Ok(display) => {
+            let (host, rest) = display.split_once(':').unwrap_or(("", &display));
+            let number = rest.split('.').next().unwrap_or("0").to_string();
+            (host.to_string(), number)
+        }
+        Err(_) => (String::new(), env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| "wayland-0".into())),
+    };
+    let host = if host.is_empty() { "unix".to_string() } else { host };
+
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or("neither XDG_CONFIG_HOME nor HOME is set")?;
+    let file = config_dir
+        .join("ibus/bus")
+        .join(format!("{}-{}-{}", machine_id.trim(), host, number));
+
+    fs::read_to_string(&file)?
+        .lines()
+        .find_map(|line| line.strip_prefix("IBUS_ADDRESS="))
+        .map(str::to_string)
+        .ok_or_else(|| format!("no IBUS_ADDRESS in {}", file.display()).into())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let connection = connection::Builder::address(ibus_address()?.as_str())?
+        .serve_at(FACTORY_PATH, engine::Factory::new())?
+        .name(BUS_NAME)?
+        .build()?;
+
+    // Requests are served on zbus's own threads until ibus-daemon closes
+    // the connection, which terminates the process
+    let _connection = connection;
+    loop {
+        std::thread::park();
+    }
+}
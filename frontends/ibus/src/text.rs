@@ -0,0 +1,69 @@
+//! Serialization of IBus's `IBusText` objects for D-Bus.
+//!
+//! IBus sends serializable GObjects as variants of the form
+//! `(name, attachments, fields...)`, so a text is
+//! `("IBusText", a{sv}, text, v:("IBusAttrList", a{sv}, av))`.
+
+use std::collections::HashMap;
+use zbus::zvariant::{StructureBuilder, Value};
+
+const ATTR_TYPE_UNDERLINE: u32 = 1;
+const ATTR_UNDERLINE_SINGLE: u32 = 1;
+
+fn attachments() -> HashMap<String, Value<'static>> {
+    HashMap::new()
+}
+
+fn serializable(name: &str, fields: StructureBuilder<'static>) -> Value<'static> {
+    let mut object = StructureBuilder::new()
+        .add_field(name.to_string())
+        .add_field(attachments());
+    for field in fields.build().expect("IBus objects have fields").into_fields() {
+        object = object.append_field(field);
+    }
+    Value::from(object.build().expect("IBus objects have fields"))
+}
+
+/// Plain text, as used for commits.
+pub fn plain(text: &str) -> Value<'static> {
+    with_attributes(text, Vec::new())
+}
+
+/// Text underlined from start to end, as used for the preedit.
+pub fn underlined(text: &str) -> Value<'static> {
+    let end = text.chars().count() as u32;
+    let underline = serializable(
+        "IBusAttribute",
+        StructureBuilder::new()
+            .add_field(ATTR_TYPE_UNDERLINE)
+            .add_field(ATTR_UNDERLINE_SINGLE)
+            .add_field(0u32)
+            .add_field(end),
+    );
+    with_attributes(text, vec![underline])
+}
+
+/// The string in an `IBusText`, e.g. the surrounding text.
+pub fn read(value: &Value<'_>) -> Option<String> {
+    let value = match value {
+        Value::Value(inner) => inner,
+        other => other,
+    };
+    let Value::Structure(text) = value else {
+        return None;
+    };
+    match text.fields() {
+        [Value::Str(name), _, Value::Str(text), ..] if name.as_str() == "IBusText" => Some(text.to_string()),
+        _ => None,
+    }
+}
+
+fn with_attributes(text: &str, attributes: Vec<Value<'static>>) -> Value<'static> {
+    let attr_list = serializable("IBusAttrList", StructureBuilder::new().add_field(attributes));
+    serializable(
+        "IBusText",
+        StructureBuilder::new()
+            .add_field(text.to_string())
+            .add_field(attr_list),
+    )
+}
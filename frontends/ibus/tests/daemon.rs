@@ -0,0 +1,158 @@
+//! Drives the engine through a private headless ibus-daemon with a scripted
+//! input context. Needs ibus-daemon, so it is ignored unless asked for with
+//! `cargo test -p bengali_keyboard_ibus -- --ignored`.
+
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::{DynamicType, OwnedObjectPath, Value};
+use zbus::MatchRule;
+
+const IBUS: &str = "org.freedesktop.IBus";
+const INPUT_CONTEXT: &str = "org.freedesktop.IBus.InputContext";
+const CAP_PREEDIT_TEXT: u32 = 1 << 0;
+const CAP_FOCUS: u32 = 1 << 3;
+const SHIFT_MASK: u32 = 1 << 0;
+const RELEASE_MASK: u32 = 1 << 30;
+const SHIFT_L: u32 = 0xffe1;
+
+struct Daemon {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn start_daemon() -> (Daemon, String) {
+    let found = Command::new("ibus-daemon").arg("--help").stdout(Stdio::null()).status().is_ok();
+    assert!(found, "ibus-daemon not found");
+
+    let dir = std::env::temp_dir().join(format!("bengali-ibus-test-{}", std::process::id()));
+    let components = dir.join("component");
+    fs::create_dir_all(&components).unwrap();
+
+    // Same component as bengali-phonetic.xml, pointing at the freshly built binary
+    let component = include_str!("../bengali-phonetic.xml").replace(
+        "/usr/libexec/ibus-engine-bengali-phonetic",
+        env!("CARGO_BIN_EXE_ibus-engine-bengali-phonetic"),
+    );
+    fs::write(components.join("bengali-phonetic.xml"), component).unwrap();
+
+    let socket = dir.join("bus");
+    let address = format!("unix:path={}", socket.display());
+    let child = Command::new("ibus-daemon")
+        .args(["--single", "--panel=disable", "--emoji-extension=disable", "--cache=none"])
+        .arg(format!("--address={address}"))
+        .env("IBUS_ADDRESS", &address)
+        .env("IBUS_COMPONENT_PATH", &components)
+        .env("XDG_CONFIG_HOME", &dir)
+        .env("XDG_CACHE_HOME", &dir)
+        .spawn()
+        .unwrap();
+    let daemon = Daemon { child, dir };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !socket.exists() {
+        assert!(Instant::now() < deadline, "ibus-daemon did not start");
+        thread::sleep(Duration::from_millis(50));
+    }
+    (daemon, address)
+}
+
+fn call<B: Serialize + DynamicType>(connection: &Connection, context: &OwnedObjectPath, method: &str, body: &B) {
+    connection
+        .call_method(Some(IBUS), context.as_str(), Some(INPUT_CONTEXT), method, body)
+        .unwrap_or_else(|e| panic!("{method}: {e}"));
+}
+
+fn text_of(value: &Value<'_>) -> Option<String> {
+    let value = match value {
+        Value::Value(inner) => inner,
+        other => other,
+    };
+    match value {
+        Value::Structure(text) => match text.fields() {
+            [Value::Str(name), _, Value::Str(text), ..] if name.as_str() == "IBusText" => Some(text.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[test]
+#[ignore = "needs ibus-daemon"]
+fn types_words_through_ibus_daemon() {
+    let (_daemon, address) = start_daemon();
+    let connection = zbus::blocking::connection::Builder::address(address.as_str())
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let context: OwnedObjectPath = connection
+        .call_method(Some(IBUS), "/org/freedesktop/IBus", Some(IBUS), "CreateInputContext", &("test"))
+        .unwrap()
+        .body()
+        .deserialize()
+        .unwrap();
+
+    // Collect commits on a separate thread so typing never blocks on them
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(INPUT_CONTEXT)
+        .unwrap()
+        .member("CommitText")
+        .unwrap()
+        .path(context.as_str())
+        .unwrap()
+        .build();
+    let signals = MessageIterator::for_match_rule(rule, &connection, None).unwrap();
+    let (commits, received) = mpsc::channel();
+    thread::spawn(move || {
+        for message in signals.flatten() {
+            let body = message.body();
+            let value: Value = body.deserialize().unwrap();
+            if let Some(text) = text_of(&value) {
+                if commits.send(text).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    call(&connection, &context, "SetCapabilities", &(CAP_PREEDIT_TEXT | CAP_FOCUS));
+    call(&connection, &context, "FocusIn", &());
+    call(&connection, &context, "SetEngine", &("bengali-phonetic"));
+
+    let key = |keyval: u32, state: u32| call(&connection, &context, "ProcessKeyEvent", &(keyval, 0u32, state));
+    for ch in "amar bangla k".chars() {
+        key(ch as u32, 0);
+    }
+    // Shift pressed and released inside a word leaves it going
+    key(SHIFT_L, 0);
+    key('S' as u32, SHIFT_MASK);
+    key(SHIFT_L, SHIFT_MASK | RELEASE_MASK);
+    for ch in "h ".chars() {
+        key(ch as u32, 0);
+    }
+
+    let mut committed = String::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while committed != "আমার বাংলা ক্ষ " {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match received.recv_timeout(remaining) {
+            Ok(text) => committed.push_str(&text),
+            Err(_) => panic!("only committed {committed:?}"),
+        }
+    }
+}
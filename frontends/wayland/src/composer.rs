@@ -0,0 +1,282 @@
+//! What the input method does with each key, apart from Wayland.
+
+use bengali_keyboard::hotkey;
+use bengali_keyboard::{
+    config, BengaliKeyboard, History, HotkeyAction, Hotkeys, InputSink, KeyboardState, Strategy, BACKSPACE,
+};
+use bengali_keyboard_evdev::keys::{self, KEY_ESC};
+use std::time::Instant;
+
+// Modifier masks of the standard xkb keymaps
+pub const MOD_SHIFT: u32 = 1 << 0;
+pub const MOD_CAPS_LOCK: u32 = 1 << 1;
+pub const MOD_CTRL: u32 = 1 << 2;
+pub const MOD_ALT: u32 = 1 << 3;
+pub const MOD_SUPER: u32 = 1 << 6;
+/// ISO_Level3_Shift, the AltGr key.
+pub const MOD_ALTGR: u32 = 1 << 7;
+
+/// Everything the input method asks of the compositor after a key, applied
+/// in this order on the next `commit`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Update {
+    /// Bytes to delete before the cursor.
+    pub delete_before: u32,
+    /// Bytes to delete after it.
+    pub delete_after: u32,
+    pub commit: String,
+    /// The word in progress; empty clears it.
+    pub preedit: String,
+}
+
+/// One input method's typing state: the word in progress is shown as a
+/// preedit and committed on a word boundary, as with IBus.
+pub struct Composer {
+    keyboard: BengaliKeyboard,
+    /// The layout's name, for the switch-layout hotkey to move on from.
+    layout: String,
+    history: History,
+    state: KeyboardState,
+    hotkeys: Hotkeys,
+    /// The text around the cursor as the application last reported it, and
+    /// the cursor's byte offset, kept up to date with our own edits.
+    surrounding: Option<(String, usize)>,
+    /// The other end of the selection the application reported, until we
+    /// edit the text.
+    anchor: Option<usize>,
+    update: Option<Update>,
+}
+
+impl Composer {
+    pub fn new(keyboard: BengaliKeyboard, history: History) -> Self {
+        let mut state = KeyboardState::new();
+        // The compositor only activates us for a text field, so we start out
+        // converting
+        state.enabled = true;
+        state.strategy = Strategy::Preedit;
+        Self {
+            keyboard,
+            layout: "phonetic".to_string(),
+            history,
+            state,
+            hotkeys: Hotkeys::default(),
+            surrounding: None,
+            anchor: None,
+            update: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.enabled
+    }
+
+    /// Replaces the default hotkeys, F10 to toggle.
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    /// Names the keyboard's layout, one of [`config::layout_names`];
+    /// phonetic unless set.
+    pub fn set_layout_name(&mut self, name: &str) {
+        self.layout = name.to_string();
+    }
+
+    pub fn layout_name(&self) -> &str {
+        &self.layout
+    }
+
+    /// The text field went away: forget the word and the text around it.
+    pub fn reset(&mut self) {
+        self.state.reset_word();
+        self.hotkeys.reset();
+        self.surrounding = None;
+        self.anchor = None;
+        self.update = None;
+    }
+
+    /// The application's text around the cursor, `cursor` and the
+    /// selection's `anchor` in bytes.
+    pub fn set_surrounding_text(&mut self, text: String, cursor: u32, anchor: u32) {
+        let cursor = (cursor as usize).min(text.len());
+        let anchor = (anchor as usize).min(text.len());
+        let valid = text.is_char_boundary(cursor) && text.is_char_boundary(anchor);
+        self.anchor = (valid && anchor != cursor).then_some(anchor);
+        self.surrounding = valid.then_some((text, cursor));
+    }
+
+    /// What to send the compositor, if anything changed since the last call.
+    pub fn take_update(&mut self) -> Option<Update> {
+        self.update.take()
+    }
+
+    /// Commits the word in progress and clears the preedit.
+    fn finish_word(&mut self) {
+        let mut output = Output {
+            update: &mut self.update,
+            surrounding: &mut self.surrounding,
+        };
+        self.state.finish_word(&self.keyboard, &self.history, &mut output);
+    }
+
+    /// Deletes the character before the cursor, if we know what it is.
+    fn delete_before(&mut self) -> bool {
+        let Some((surrounding, cursor)) = &mut self.surrounding else {
+            return false;
+        };
+        let Some(previous) = surrounding[..*cursor].chars().next_back() else {
+            return false;
+        };
+        *cursor -= previous.len_utf8();
+        surrounding.remove(*cursor);
+        self.update.get_or_insert_with(Update::default).delete_before += previous.len_utf8() as u32;
+        true
+    }
+
+    /// Replaces the selection with its conversion.
+    fn convert_selection(&mut self) {
+        self.finish_word();
+        let (Some((surrounding, cursor)), Some(anchor)) = (&mut self.surrounding, self.anchor.take()) else {
+            return;
+        };
+        let (start, end) = (anchor.min(*cursor), anchor.max(*cursor));
+        let converted = self.keyboard.convert_text(&surrounding[start..end]);
+        let update = self.update.get_or_insert_with(Update::default);
+        update.delete_before += (*cursor - start) as u32;
+        update.delete_after += (end - *cursor) as u32;
+        update.commit.push_str(&converted);
+        surrounding.replace_range(start..end, &converted);
+        *cursor = start + converted.len();
+    }
+
+    /// Follows a key event for the hotkeys and does what one it completes
+    /// is bound to. Returns whether it completed one.
+    fn hotkey(&mut self, code: u16, modifiers: u32, press: bool) -> bool {
+        let Some(key) = keys::hotkey_key(code) else {
+            return false;
+        };
+        let held = [
+            (MOD_CTRL, hotkey::CTRL),
+            (MOD_ALT, hotkey::ALT),
+            (MOD_SHIFT, hotkey::SHIFT),
+            (MOD_SUPER, hotkey::SUPER),
+        ]
+        .into_iter()
+        .filter(|&(mask, _)| modifiers & mask != 0)
+        .fold(0, |held, (_, bit)| held | bit);
+        let Some(action) = self.hotkeys.key(key, held, press, Instant::now()) else {
+            return false;
+        };
+        match action {
+            HotkeyAction::Toggle => {
+                self.finish_word();
+                self.state.toggle();
+            }
+            HotkeyAction::ConvertSelection => self.convert_selection(),
+            HotkeyAction::ForceEnglish => {
+                let mut output = Output {
+                    update: &mut self.update,
+                    surrounding: &mut self.surrounding,
+                };
+                self.state
+                    .force_english_word(&self.keyboard, &self.history, &mut output);
+            }
+            HotkeyAction::SwitchLayout => {
+                let next = config::next_layout(&self.layout);
+                match config::load_keyboard(next) {
+                    Ok(keyboard) => {
+                        self.finish_word();
+                        self.keyboard = keyboard;
+                        self.layout = next.to_string();
+                    }
+                    Err(e) => eprintln!("bengali-wayland: {e}"),
+                }
+            }
+        }
+        true
+    }
+
+    /// Handles a key press, `modifiers` being the xkb modifier masks in
+    /// effect. Returns whether the key was consumed; the caller forwards
+    /// the others to the application.
+    pub fn press(&mut self, code: u16, modifiers: u32) -> bool {
+        let consumed = self.hotkey(code, modifiers, true) || self.type_key(code, modifiers);
+        if self.update.is_some() {
+            // Our edits leave the selection behind
+            self.anchor = None;
+        }
+        consumed
+    }
+
+    /// Handles a key release. Releases are always forwarded, but one may
+    /// complete a hotkey tapped on a modifier.
+    pub fn release(&mut self, code: u16, modifiers: u32) {
+        self.hotkey(code, modifiers, false);
+    }
+
+    fn type_key(&mut self, code: u16, modifiers: u32) -> bool {
+        if !self.state.enabled {
+            return false;
+        }
+
+        if keys::is_modifier(code) {
+            // Shift and AltGr change the next key; the word goes on
+            return false;
+        }
+
+        if modifiers & (MOD_CTRL | MOD_ALT | MOD_SUPER) != 0 {
+            // Shortcuts go to the application, with the word finished first
+            self.finish_word();
+            return false;
+        }
+
+        let shift = modifiers & MOD_SHIFT != 0;
+        let caps_lock = modifiers & MOD_CAPS_LOCK != 0;
+        let mut output = Output {
+            update: &mut self.update,
+            surrounding: &mut self.surrounding,
+        };
+        match (code, keys::key_char(code, shift, caps_lock)) {
+            (KEY_ESC, _) => self.state.cancel_word(&mut output),
+            (_, Some(ch)) => {
+                let altgr = modifiers & MOD_ALTGR != 0;
+                let consumed = self.state.process_key(&self.keyboard, &self.history, ch, altgr, &mut output);
+                // Deleting through the protocol keeps it in order with our
+                // commits, which a forwarded key would not be
+                consumed || (ch == BACKSPACE && self.delete_before())
+            }
+            _ => {
+                self.state.finish_word(&self.keyboard, &self.history, &mut output);
+                false
+            }
+        }
+    }
+}
+
+/// Writes what [`KeyboardState`] sends to the next [`Update`], keeping the
+/// surrounding text in step with our commits.
+struct Output<'a> {
+    update: &'a mut Option<Update>,
+    surrounding: &'a mut Option<(String, usize)>,
+}
+
+impl Output<'_> {
+    fn pending(&mut self) -> &mut Update {
+        self.update.get_or_insert_with(Update::default)
+    }
+}
+
+impl InputSink for Output<'_> {
+    fn send_backspaces(&mut self, _count: usize) {}
+
+    fn send_text(&mut self, text: &str) {
+        if let Some((surrounding, cursor)) = self.surrounding {
+            surrounding.insert_str(*cursor, text);
+            *cursor += text.len();
+        }
+        self.pending().commit.push_str(text);
+    }
+
+    fn update_preedit(&mut self, text: &str) {
+        self.pending().preedit = text.to_string();
+    }
+}
@@ -0,0 +1,270 @@
+//! Native Wayland input method for wlroots compositors (Sway, Hyprland).
+//!
+//! Instead of injecting keystrokes, this speaks `zwp_input_method_v2`: the
+//! compositor activates us for the focused text field, we grab the keyboard,
+//! show the word in progress with `set_preedit_string` and finish it with
+//! `commit_string`. Backspace past the word deletes through
+//! `delete_surrounding_text`, whose lengths are in bytes; the convert
+//! selection hotkey replaces the selection the same way. Keys we do not
+//! consume go back to the application through a `zwp_virtual_keyboard_v1`
+//! with the grab's own keymap.
+//!
+//! Key codes are read as a US keyboard, as in the evdev daemon. Held keys
+//! do not repeat while the keyboard is grabbed.
+
+pub mod composer;
+
+use composer::Composer;
+use std::collections::HashSet;
+use std::error::Error;
+use std::os::fd::AsFd;
+use wayland_client::protocol::wl_keyboard::KeyState;
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_keyboard_grab_v2::{
+    self, ZwpInputMethodKeyboardGrabV2,
+};
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_manager_v2::ZwpInputMethodManagerV2;
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_v2::{self, ZwpInputMethodV2};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+
+/// Activation state sent since the last `done`.
+#[derive(Default)]
+struct Pending {
+    active: Option<bool>,
+    surrounding: Option<(String, u32, u32)>,
+}
+
+struct State {
+    composer: Composer,
+    seat: Option<WlSeat>,
+    input_method_manager: Option<ZwpInputMethodManagerV2>,
+    virtual_keyboard_manager: Option<ZwpVirtualKeyboardManagerV1>,
+    input_method: Option<ZwpInputMethodV2>,
+    virtual_keyboard: Option<ZwpVirtualKeyboardV1>,
+    grab: Option<ZwpInputMethodKeyboardGrabV2>,
+    pending: Pending,
+    /// How many `done` events have arrived, which `commit` must echo.
+    serial: u32,
+    /// The virtual keyboard has a keymap and may send keys.
+    has_keymap: bool,
+    /// Depressed, latched and locked modifiers, and the group.
+    modifiers: [u32; 4],
+    /// Keys whose press was consumed, so their release is too.
+    consumed: HashSet<u32>,
+    /// Why the loop should stop.
+    error: Option<String>,
+}
+
+impl State {
+    fn new(composer: Composer) -> Self {
+        Self {
+            composer,
+            seat: None,
+            input_method_manager: None,
+            virtual_keyboard_manager: None,
+            input_method: None,
+            virtual_keyboard: None,
+            grab: None,
+            pending: Pending::default(),
+            serial: 0,
+            has_keymap: false,
+            modifiers: [0; 4],
+            consumed: HashSet::new(),
+            error: None,
+        }
+    }
+
+    /// Sends what the last key changed, if anything.
+    fn send_update(&mut self) {
+        let (Some(input_method), Some(update)) = (&self.input_method, self.composer.take_update()) else {
+            return;
+        };
+        if update.delete_before > 0 || update.delete_after > 0 {
+            input_method.delete_surrounding_text(update.delete_before, update.delete_after);
+        }
+        if !update.commit.is_empty() {
+            input_method.commit_string(update.commit);
+        }
+        let cursor = update.preedit.len() as i32;
+        input_method.set_preedit_string(update.preedit, cursor, cursor);
+        input_method.commit(self.serial);
+    }
+
+    fn key(&mut self, time: u32, key: u32, state: WEnum<KeyState>) {
+        let pressed = state == WEnum::Value(KeyState::Pressed);
+        if pressed {
+            let [depressed, latched, locked, _] = self.modifiers;
+            let consumed = u16::try_from(key).is_ok_and(|code| self.composer.press(code, depressed | latched | locked));
+            self.send_update();
+            if consumed {
+                self.consumed.insert(key);
+                return;
+            }
+        } else {
+            if let Ok(code) = u16::try_from(key) {
+                let [depressed, latched, locked, _] = self.modifiers;
+                self.composer.release(code, depressed | latched | locked);
+                self.send_update();
+            }
+            if self.consumed.remove(&key) {
+                return;
+            }
+        }
+        if let (Some(virtual_keyboard), true) = (&self.virtual_keyboard, self.has_keymap) {
+            virtual_keyboard.key(time, key, u32::from(state));
+        }
+    }
+
+    /// Applies what arrived since the last `done`.
+    fn done(&mut self, qh: &QueueHandle<Self>) {
+        self.serial = self.serial.wrapping_add(1);
+        let pending = std::mem::take(&mut self.pending);
+        match pending.active {
+            Some(true) => {
+                self.composer.reset();
+                if self.grab.is_none() {
+                    if let Some(input_method) = &self.input_method {
+                        self.grab = Some(input_method.grab_keyboard(qh, ()));
+                    }
+                }
+            }
+            Some(false) => {
+                self.composer.reset();
+                if let Some(grab) = self.grab.take() {
+                    grab.release();
+                }
+                self.consumed.clear();
+            }
+            None => {}
+        }
+        if let Some((text, cursor, anchor)) = pending.surrounding {
+            self.composer.set_surrounding_text(text, cursor, anchor);
+        }
+    }
+}
+
+impl Dispatch<WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let wl_registry::Event::Global { name, interface, .. } = event else {
+            return;
+        };
+        if interface == WlSeat::interface().name && state.seat.is_none() {
+            state.seat = Some(registry.bind(name, 1, qh, ()));
+        } else if interface == ZwpInputMethodManagerV2::interface().name {
+            state.input_method_manager = Some(registry.bind(name, 1, qh, ()));
+        } else if interface == ZwpVirtualKeyboardManagerV1::interface().name {
+            state.virtual_keyboard_manager = Some(registry.bind(name, 1, qh, ()));
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwpInputMethodV2,
+        event: zwp_input_method_v2::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_input_method_v2::Event::Activate => {
+                state.pending = Pending {
+                    active: Some(true),
+                    surrounding: None,
+                }
+            }
+            zwp_input_method_v2::Event::Deactivate => state.pending.active = Some(false),
+            zwp_input_method_v2::Event::SurroundingText { text, cursor, anchor } => {
+                state.pending.surrounding = Some((text, cursor, anchor))
+            }
+            zwp_input_method_v2::Event::Done => state.done(qh),
+            zwp_input_method_v2::Event::Unavailable => {
+                state.error = Some("another input method is already running".to_string())
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodKeyboardGrabV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwpInputMethodKeyboardGrabV2,
+        event: zwp_input_method_keyboard_grab_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_input_method_keyboard_grab_v2::Event::Keymap { format, fd, size } => {
+                // Forwarded keys must mean what they did on the real keyboard
+                if let Some(virtual_keyboard) = &state.virtual_keyboard {
+                    virtual_keyboard.keymap(u32::from(format), fd.as_fd(), size);
+                    state.has_keymap = true;
+                }
+            }
+            zwp_input_method_keyboard_grab_v2::Event::Key {
+                time,
+                key,
+                state: key_state,
+                ..
+            } => state.key(time, key, key_state),
+            zwp_input_method_keyboard_grab_v2::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => {
+                state.modifiers = [mods_depressed, mods_latched, mods_locked, group];
+                if let (Some(virtual_keyboard), true) = (&state.virtual_keyboard, state.has_keymap) {
+                    virtual_keyboard.modifiers(mods_depressed, mods_latched, mods_locked, group);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+wayland_client::delegate_noop!(State: ignore WlSeat);
+wayland_client::delegate_noop!(State: ZwpInputMethodManagerV2);
+wayland_client::delegate_noop!(State: ZwpVirtualKeyboardManagerV1);
+wayland_client::delegate_noop!(State: ZwpVirtualKeyboardV1);
+
+/// Serves as the input method of the first seat on `connection` until the
+/// compositor goes away or another input method takes over.
+pub fn run(connection: Connection, composer: Composer) -> Result<(), Box<dyn Error>> {
+    let mut queue = connection.new_event_queue();
+    let qh = queue.handle();
+    connection.display().get_registry(&qh, ());
+    let mut state = State::new(composer);
+    queue.roundtrip(&mut state)?;
+
+    let seat = state.seat.clone().ok_or("the compositor has no seat")?;
+    let manager = state
+        .input_method_manager
+        .clone()
+        .ok_or("the compositor does not support zwp_input_method_manager_v2")?;
+    let virtual_keyboards = state
+        .virtual_keyboard_manager
+        .clone()
+        .ok_or("the compositor does not support zwp_virtual_keyboard_manager_v1")?;
+    state.input_method = Some(manager.get_input_method(&seat, &qh, ()));
+    state.virtual_keyboard = Some(virtual_keyboards.create_virtual_keyboard(&seat, &qh, ()));
+
+    while state.error.is_none() {
+        queue.blocking_dispatch(&mut state)?;
+    }
+    Err(state.error.unwrap_or_default().into())
+}
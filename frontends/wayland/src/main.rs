@@ -0,0 +1,58 @@
+//! `bengali-wayland`: the Bengali input method for compositors with
+//! `zwp_input_method_v2`, such as Sway and Hyprland. Start it from the
+//! compositor's configuration, e.g. `exec bengali-wayland` in Sway.
+
+use bengali_keyboard::{config, History};
+use bengali_keyboard_wayland::composer::Composer;
+use std::env;
+use std::process;
+use wayland_client::Connection;
+
+const USAGE: &str = "\
+Usage: bengali-wayland [OPTIONS]
+
+Bengali input method for Wayland compositors with input-method-unstable-v2.
+F10 toggles conversion, unless hotkeys.toml in the config directory says
+otherwise.
+
+Options:
+  -l, --layout LAYOUT  phonetic (default), bijoy, national or probhat
+  -h, --help           Show this help";
+
+fn parse_args() -> Result<String, String> {
+    let mut layout = "phonetic".to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "-l" | "--layout" => layout = args.next().ok_or_else(|| format!("{arg} needs a value"))?,
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    Ok(layout)
+}
+
+fn run(layout: String) -> Result<(), String> {
+    let keyboard = config::load_keyboard(&layout).map_err(|e| e.to_string())?;
+    let hotkeys = config::load_hotkeys().map_err(|e| e.to_string())?;
+    let history = config::load_history().unwrap_or_else(|e| {
+        eprintln!("bengali-wayland: {e}, ignoring the history");
+        History::new()
+    });
+    let connection = Connection::connect_to_env().map_err(|e| e.to_string())?;
+    let mut composer = Composer::new(keyboard, history);
+    composer.set_layout_name(&layout);
+    composer.set_hotkeys(hotkeys);
+    bengali_keyboard_wayland::run(connection, composer).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(message) = result {
+        eprintln!("bengali-wayland: {message}");
+        process::exit(1);
+    }
+}
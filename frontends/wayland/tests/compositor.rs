@@ -0,0 +1,475 @@
+//! Runs the input method against a minimal in-process compositor that
+//! offers a seat, `zwp_input_method_manager_v2` and
+//! `zwp_virtual_keyboard_manager_v1`, and applies what it commits to one
+//! text field the way text-input-v3 would.
+
+use bengali_keyboard::{BengaliKeyboard, FixedLayout, History, Hotkeys};
+use bengali_keyboard_evdev::keys::{self, KEY_BACKSPACE, KEY_F10, KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_SPACE};
+use bengali_keyboard_wayland::composer::{Composer, MOD_ALT, MOD_ALTGR, MOD_CTRL, MOD_SHIFT};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use wayland_client::Connection;
+use wayland_protocols_misc::zwp_input_method_v2::server::zwp_input_method_keyboard_grab_v2::{
+    self, ZwpInputMethodKeyboardGrabV2,
+};
+use wayland_protocols_misc::zwp_input_method_v2::server::zwp_input_method_manager_v2::{self, ZwpInputMethodManagerV2};
+use wayland_protocols_misc::zwp_input_method_v2::server::zwp_input_method_v2::{self, ZwpInputMethodV2};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::server::zwp_virtual_keyboard_manager_v1::{
+    self, ZwpVirtualKeyboardManagerV1,
+};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::server::zwp_virtual_keyboard_v1::{self, ZwpVirtualKeyboardV1};
+use wayland_server::backend::{ClientData, ClientId, DisconnectReason};
+use wayland_server::protocol::wl_keyboard::{KeyState, KeymapFormat};
+use wayland_server::protocol::wl_seat::{self, WlSeat};
+use wayland_server::{Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, New};
+
+const KEYMAP: &str = "xkb_keymap { };\n";
+
+/// What the input method asked for since its last `commit`.
+#[derive(Default)]
+struct Pending {
+    delete_before: u32,
+    delete_after: u32,
+    commit: String,
+    preedit: String,
+}
+
+#[derive(Default)]
+struct Compositor {
+    input_method: Option<ZwpInputMethodV2>,
+    grab: Option<ZwpInputMethodKeyboardGrabV2>,
+    /// The focused text field, with the cursor at a byte offset.
+    text: String,
+    cursor: usize,
+    preedit: String,
+    pending: Pending,
+    /// `done` events sent, and the serials the input method committed.
+    dones: u32,
+    commits: Vec<u32>,
+    /// The keymap size the virtual keyboard was given.
+    virtual_keymap: Option<u32>,
+    /// Keys that came back through the virtual keyboard, with their state.
+    forwarded: Vec<(u32, u32)>,
+    key_serial: u32,
+}
+
+impl Compositor {
+    fn apply(&mut self, serial: u32) {
+        let pending = std::mem::take(&mut self.pending);
+        let start = self.cursor - pending.delete_before as usize;
+        let end = self.cursor + pending.delete_after as usize;
+        self.text.replace_range(start..end, &pending.commit);
+        self.cursor = start + pending.commit.len();
+        self.preedit = pending.preedit;
+        self.commits.push(serial);
+    }
+}
+
+struct NoData;
+
+impl ClientData for NoData {
+    fn initialized(&self, _: ClientId) {}
+    fn disconnected(&self, _: ClientId, _: DisconnectReason) {}
+}
+
+impl GlobalDispatch<WlSeat, ()> for Compositor {
+    fn bind(_: &mut Self, _: &DisplayHandle, _: &Client, seat: New<WlSeat>, _: &(), init: &mut DataInit<'_, Self>) {
+        init.init(seat, ());
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlSeat,
+        _: wl_seat::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ZwpInputMethodManagerV2, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        manager: New<ZwpInputMethodManagerV2>,
+        _: &(),
+        init: &mut DataInit<'_, Self>,
+    ) {
+        init.init(manager, ());
+    }
+}
+
+impl Dispatch<ZwpInputMethodManagerV2, ()> for Compositor {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpInputMethodManagerV2,
+        request: zwp_input_method_manager_v2::Request,
+        _: &(),
+        _: &DisplayHandle,
+        init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_input_method_manager_v2::Request::GetInputMethod { input_method, .. } = request {
+            state.input_method = Some(init.init(input_method, ()));
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for Compositor {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpInputMethodV2,
+        request: zwp_input_method_v2::Request,
+        _: &(),
+        _: &DisplayHandle,
+        init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwp_input_method_v2::Request::CommitString { text } => state.pending.commit = text,
+            zwp_input_method_v2::Request::SetPreeditString { text, .. } => state.pending.preedit = text,
+            zwp_input_method_v2::Request::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => {
+                state.pending.delete_before = before_length;
+                state.pending.delete_after = after_length;
+            }
+            zwp_input_method_v2::Request::Commit { serial } => state.apply(serial),
+            zwp_input_method_v2::Request::GrabKeyboard { keyboard } => {
+                let grab = init.init(keyboard, ());
+                let mut keymap = tempfile();
+                keymap.write_all(KEYMAP.as_bytes()).unwrap();
+                grab.keymap(KeymapFormat::XkbV1, keymap.as_fd(), KEYMAP.len() as u32);
+                state.grab = Some(grab);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodKeyboardGrabV2, ()> for Compositor {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpInputMethodKeyboardGrabV2,
+        request: zwp_input_method_keyboard_grab_v2::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_input_method_keyboard_grab_v2::Request::Release = request {
+            state.grab = None;
+        }
+    }
+}
+
+impl GlobalDispatch<ZwpVirtualKeyboardManagerV1, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        manager: New<ZwpVirtualKeyboardManagerV1>,
+        _: &(),
+        init: &mut DataInit<'_, Self>,
+    ) {
+        init.init(manager, ());
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &ZwpVirtualKeyboardManagerV1,
+        request: zwp_virtual_keyboard_manager_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwp_virtual_keyboard_manager_v1::Request::CreateVirtualKeyboard { id, .. } = request {
+            init.init(id, ());
+        }
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for Compositor {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &ZwpVirtualKeyboardV1,
+        request: zwp_virtual_keyboard_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwp_virtual_keyboard_v1::Request::Keymap { size, .. } => state.virtual_keymap = Some(size),
+            zwp_virtual_keyboard_v1::Request::Key {
+                key, state: key_state, ..
+            } => state.forwarded.push((key, key_state)),
+            _ => {}
+        }
+    }
+}
+
+fn tempfile() -> std::fs::File {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!("bengali-wayland-keymap-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+    let path = std::env::temp_dir().join(name);
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    let _ = std::fs::remove_file(&path);
+    file
+}
+
+/// The compositor, with the input method connected on its own thread.
+struct Session {
+    display: Display<Compositor>,
+    compositor: Compositor,
+    input_method: Option<JoinHandle<()>>,
+}
+
+impl Session {
+    fn start(keyboard: BengaliKeyboard) -> Self {
+        Self::with_composer(Composer::new(keyboard, History::new()))
+    }
+
+    fn with_composer(composer: Composer) -> Self {
+        let display = Display::<Compositor>::new().unwrap();
+        let mut handle = display.handle();
+        handle.create_global::<Compositor, WlSeat, ()>(7, ());
+        handle.create_global::<Compositor, ZwpInputMethodManagerV2, ()>(1, ());
+        handle.create_global::<Compositor, ZwpVirtualKeyboardManagerV1, ()>(1, ());
+
+        let (server, client) = UnixStream::pair().unwrap();
+        handle.insert_client(server, Arc::new(NoData)).unwrap();
+        let input_method = thread::spawn(move || {
+            let connection = Connection::from_socket(client).unwrap();
+            // Returns once the compositor is gone
+            let _ = bengali_keyboard_wayland::run(connection, composer);
+        });
+
+        let mut session = Self {
+            display,
+            compositor: Compositor::default(),
+            input_method: Some(input_method),
+        };
+        session.pump_until(|compositor| compositor.input_method.is_some());
+        session
+    }
+
+    fn pump_until(&mut self, done: impl Fn(&Compositor) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            self.display.dispatch_clients(&mut self.compositor).unwrap();
+            self.display.flush_clients().unwrap();
+            if done(&self.compositor) {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "timed out; field is {:?}",
+                self.compositor.text
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Focuses a text field holding `text`, with the cursor at its end.
+    fn activate(&mut self, text: &str) {
+        self.select(text, text.len(), text.len());
+    }
+
+    /// Focuses a text field holding `text`, with the cursor at `cursor` and
+    /// the selection running from there to `anchor`.
+    fn select(&mut self, text: &str, cursor: usize, anchor: usize) {
+        self.compositor.text = text.to_string();
+        self.compositor.cursor = cursor;
+        let input_method = self.compositor.input_method.as_ref().unwrap();
+        input_method.activate();
+        input_method.surrounding_text(text.to_string(), cursor as u32, anchor as u32);
+        input_method.done();
+        self.compositor.dones += 1;
+        self.pump_until(|compositor| compositor.grab.is_some() && compositor.virtual_keymap.is_some());
+    }
+
+    fn deactivate(&mut self) {
+        let input_method = self.compositor.input_method.as_ref().unwrap();
+        input_method.deactivate();
+        input_method.done();
+        self.compositor.dones += 1;
+        self.pump_until(|compositor| compositor.grab.is_none());
+    }
+
+    fn key(&mut self, code: u16, modifiers: u32) {
+        for state in [KeyState::Pressed, KeyState::Released] {
+            self.key_event(code, modifiers, state);
+        }
+    }
+
+    /// Presses or releases one key with `modifiers` in effect.
+    fn key_event(&mut self, code: u16, modifiers: u32, state: KeyState) {
+        let compositor = &mut self.compositor;
+        let grab = compositor.grab.as_ref().unwrap();
+        grab.modifiers(compositor.key_serial, modifiers, 0, 0, 0);
+        compositor.key_serial += 1;
+        grab.key(compositor.key_serial, 0, u32::from(code), state);
+    }
+
+    /// Types `text` on a US keyboard, `\x08` being backspace.
+    fn type_text(&mut self, text: &str) {
+        for ch in text.chars() {
+            let (code, shift) = match ch {
+                '\x08' => (KEY_BACKSPACE, false),
+                _ => keys::char_key(ch).unwrap(),
+            };
+            self.key(code, if shift { MOD_SHIFT } else { 0 });
+        }
+    }
+
+    /// Waits for the field and preedit to read `text` and `preedit`.
+    fn expect(&mut self, text: &str, preedit: &str) {
+        self.pump_until(|compositor| compositor.text == text && compositor.preedit == preedit);
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // Closing the display disconnects the input method
+        drop(std::mem::replace(&mut self.display, Display::new().unwrap()));
+        if let Some(input_method) = self.input_method.take() {
+            let _ = input_method.join();
+        }
+    }
+}
+
+#[test]
+fn word_is_shown_as_preedit_and_committed_at_a_space() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("");
+    session.type_text("ami");
+    session.expect("", "আমি");
+    session.type_text(" kor");
+    session.expect("আমি ", "কর");
+    session.type_text("!");
+    session.expect("আমি কর!", "");
+    // Every commit answers the latest done
+    assert_eq!(session.compositor.commits.last(), Some(&session.compositor.dones));
+}
+
+#[test]
+fn backspace_edits_the_word_then_deletes_surrounding_text() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("কখ");
+    session.type_text("ami\x08");
+    session.expect("কখ", "আম");
+    session.type_text("\x08\x08");
+    session.expect("কখ", "");
+    // Past the word, backspace deletes a whole character: three bytes
+    session.type_text("\x08");
+    session.expect("ক", "");
+    assert!(session.compositor.forwarded.is_empty());
+}
+
+#[test]
+fn unconsumed_keys_go_through_the_virtual_keyboard() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("");
+    assert_eq!(session.compositor.virtual_keymap, Some(KEYMAP.len() as u32));
+
+    session.type_text("am");
+    session.key(KEY_LEFTCTRL, 0);
+    session.key(46, MOD_CTRL); // Ctrl+C
+                               // The shortcut finishes the word first
+    session.expect("আম", "");
+    session.pump_until(|compositor| compositor.forwarded.len() == 4);
+    assert_eq!(session.compositor.forwarded, [(29, 1), (29, 0), (46, 1), (46, 0)]);
+}
+
+#[test]
+fn f10_toggles_conversion() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("");
+    session.type_text("am");
+    session.key(KEY_F10, 0);
+    session.expect("আম", "");
+    session.type_text("i");
+    session.pump_until(|compositor| compositor.forwarded.len() == 2);
+    assert_eq!(session.compositor.forwarded, [(23, 1), (23, 0)]);
+}
+
+#[test]
+fn hotkeys_convert_the_selection_and_toggle() {
+    let mut composer = Composer::new(BengaliKeyboard::new(), History::new());
+    composer.set_hotkeys(Hotkeys::from_toml("toggle = \"Ctrl+Space\"\nconvert_selection = \"Ctrl+Alt+B\"").unwrap());
+    let mut session = Session::with_composer(composer);
+    session.select("x ami kor", 9, 2);
+    session.key(48, MOD_CTRL | MOD_ALT); // Ctrl+Alt+B
+    session.expect("x আমি কর", "");
+
+    // With the cursor at the start of the selection
+    session.select("ami x", 0, 3);
+    session.key(48, MOD_CTRL | MOD_ALT);
+    session.expect("আমি x", "");
+
+    session.key(KEY_SPACE, MOD_CTRL);
+    session.type_text("k");
+    session.pump_until(|compositor| compositor.forwarded.len() == 2);
+    assert_eq!(session.compositor.forwarded, [(37, 1), (37, 0)]);
+}
+
+#[test]
+fn altgr_types_the_fixed_layouts_altgr_level() {
+    let national = FixedLayout::builtin("national").unwrap();
+    let mut session = Session::start(BengaliKeyboard::with_fixed_layout(national));
+    session.activate("");
+    session.key(33, MOD_ALTGR); // AltGr+F
+    session.expect("", "আ");
+    session.key(KEY_LEFTSHIFT, 0);
+    session.type_text(" ");
+    session.expect("আ ", "");
+}
+
+#[test]
+fn shift_inside_a_word_leaves_it_going() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("");
+    session.type_text("k");
+    session.key_event(KEY_LEFTSHIFT, 0, KeyState::Pressed);
+    let (s, _) = keys::char_key('S').unwrap();
+    session.key(s, MOD_SHIFT);
+    session.key_event(KEY_LEFTSHIFT, MOD_SHIFT, KeyState::Released);
+    session.type_text("h");
+    session.expect("", "ক্ষ");
+    session.type_text(" ");
+    session.expect("ক্ষ ", "");
+}
+
+#[test]
+fn deactivating_releases_the_keyboard_and_drops_the_word() {
+    let mut session = Session::start(BengaliKeyboard::new());
+    session.activate("");
+    session.type_text("ami");
+    session.expect("", "আমি");
+    session.deactivate();
+
+    session.activate("x");
+    session.type_text("k ");
+    session.expect("xক ", "");
+}
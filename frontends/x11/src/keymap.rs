@@ -0,0 +1,90 @@
+//! The X server's key code to keysym table, as `GetKeyboardMapping`
+//! returns it.
+
+/// Keysym 0, an unmapped slot.
+pub const NO_SYMBOL: u32 = 0;
+
+// Modifier masks from X.h
+pub const SHIFT_MASK: u16 = 1 << 0;
+pub const LOCK_MASK: u16 = 1 << 1;
+pub const CONTROL_MASK: u16 = 1 << 2;
+pub const MOD1_MASK: u16 = 1 << 3;
+pub const MOD4_MASK: u16 = 1 << 6;
+/// AltGr (ISO_Level3_Shift) on the usual keymaps.
+pub const MOD5_MASK: u16 = 1 << 7;
+
+pub struct Keymap {
+    min_keycode: u8,
+    per_keycode: usize,
+    keysyms: Vec<u32>,
+}
+
+impl Keymap {
+    /// `keysyms` holds `per_keycode` keysyms for each key code from
+    /// `min_keycode` up.
+    pub fn new(min_keycode: u8, per_keycode: u8, keysyms: Vec<u32>) -> Self {
+        Self {
+            min_keycode,
+            per_keycode: usize::from(per_keycode).max(1),
+            keysyms,
+        }
+    }
+
+    fn slots(&self, keycode: u8) -> &[u32] {
+        let Some(index) = keycode.checked_sub(self.min_keycode) else {
+            return &[];
+        };
+        let start = usize::from(index) * self.per_keycode;
+        self.keysyms.get(start..start + self.per_keycode).unwrap_or(&[])
+    }
+
+    /// The keysym `keycode` types in the first group with `modifiers` held.
+    /// Only Shift and Caps Lock choose the level; other modifiers are the
+    /// caller's business.
+    pub fn keysym(&self, keycode: u8, modifiers: u16) -> u32 {
+        let slots = self.slots(keycode);
+        let lower = slots.first().copied().unwrap_or(NO_SYMBOL);
+        let upper = match slots.get(1).copied().unwrap_or(NO_SYMBOL) {
+            // A lone letter keysym stands for both cases, as in Xlib
+            NO_SYMBOL => char::from_u32(lower)
+                .filter(|_| lower < 0x80)
+                .map_or(lower, |ch| u32::from(ch.to_ascii_uppercase())),
+            upper => upper,
+        };
+        let is_letter = lower < 0x80 && char::from_u32(lower).is_some_and(|ch| ch.is_ascii_alphabetic());
+        let shift = modifiers & SHIFT_MASK != 0;
+        let caps_lock = modifiers & LOCK_MASK != 0 && is_letter;
+        if shift != caps_lock {
+            upper
+        } else {
+            lower
+        }
+    }
+
+    /// The key code and whether it needs Shift, for the first key that
+    /// types `keysym`.
+    pub fn keycode(&self, keysym: u32) -> Option<(u8, bool)> {
+        (self.min_keycode..=u8::MAX).find_map(|keycode| {
+            let level = self.slots(keycode).iter().take(2).position(|&slot| slot == keysym)?;
+            Some((keycode, level == 1))
+        })
+    }
+
+    /// Key codes with nothing on them, free to carry the text we type.
+    pub fn unused_keycodes(&self) -> Vec<u8> {
+        let count = self.keysyms.len() / self.per_keycode;
+        (0..count)
+            .filter_map(|index| u8::try_from(usize::from(self.min_keycode) + index).ok())
+            .filter(|&keycode| self.slots(keycode).iter().all(|&slot| slot == NO_SYMBOL))
+            .collect()
+    }
+}
+
+/// The keysym for typing `ch`: Latin-1 keysyms are their code points,
+/// anything else is the Unicode keysym range.
+pub fn char_to_keysym(ch: char) -> u32 {
+    match u32::from(ch) {
+        code @ (0x20..=0x7e | 0xa0..=0xff) => code,
+        code => 0x0100_0000 | code,
+    }
+}
@@ -0,0 +1,203 @@
+//! Bengali typing for plain X11 desktops, the way the Win32 front-end does
+//! it: watch every key, convert, and type the result into whatever window
+//! has focus.
+//!
+//! The keyboard is grabbed so keys reach us instead of the application.
+//! [`Translator`] runs them through the same [`KeyboardState`] as
+//! `keyboard_hook_proc` and says what to do instead: let a key through,
+//! erase characters or type text. [`session`] does that with XTest, typing
+//! Bengali on otherwise unused key codes remapped to its keysyms, as
+//! `send_unicode_text` does with `KEYEVENTF_UNICODE`.
+//!
+//! The hotkeys (F10 by default) toggle conversion, convert the PRIMARY
+//! selection, type the next word in English and switch layouts. Ctrl, Alt
+//! and Super shortcuts pass through and end the word; AltGr (Mod5) types
+//! the AltGr level of the fixed layouts.
+
+pub mod keymap;
+pub mod session;
+
+use bengali_keyboard::hotkey::{self, Key};
+use bengali_keyboard::keysym::{is_modifier_keysym, keysym_to_char};
+use bengali_keyboard::{config, BengaliKeyboard, History, HotkeyAction, Hotkeys, InputSink, KeyboardState};
+use keymap::{Keymap, CONTROL_MASK, MOD1_MASK, MOD4_MASK, MOD5_MASK, SHIFT_MASK};
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// What to do about one key event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Pass the key on to the application.
+    Forward {
+        keycode: u8,
+        press: bool,
+    },
+    /// Erase this many characters before the cursor.
+    Backspaces(usize),
+    Text(String),
+    /// Type the conversion of the selected text over it.
+    ConvertSelection,
+}
+
+/// Collects what [`KeyboardState`] writes as actions.
+#[derive(Default)]
+struct Actions(Vec<Action>);
+
+impl InputSink for Actions {
+    fn send_backspaces(&mut self, count: usize) {
+        self.0.push(Action::Backspaces(count));
+    }
+
+    fn send_text(&mut self, text: &str) {
+        self.0.push(Action::Text(text.to_string()));
+    }
+}
+
+pub struct Translator {
+    keyboard: BengaliKeyboard,
+    /// The layout's name, for the switch-layout hotkey to move on from.
+    layout: String,
+    state: KeyboardState,
+    /// Spellings picked from suggestions in the other front-ends.
+    history: History,
+    hotkeys: Hotkeys,
+    /// Keys whose press was swallowed, so their release is too.
+    swallowed: HashSet<u8>,
+}
+
+impl Translator {
+    /// A translator for `keyboard`, with conversion on.
+    pub fn new(keyboard: BengaliKeyboard) -> Self {
+        let mut state = KeyboardState::new();
+        state.enabled = true;
+        Self {
+            keyboard,
+            layout: "phonetic".to_string(),
+            state,
+            history: History::new(),
+            hotkeys: Hotkeys::default(),
+            swallowed: HashSet::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.enabled
+    }
+
+    /// Replaces the default hotkeys, F10 to toggle.
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    /// Converts with the spellings in `history`, usually
+    /// [`config::load_history`]; empty unless set.
+    pub fn set_history(&mut self, history: History) {
+        self.history = history;
+    }
+
+    /// Names the keyboard's layout, one of [`config::layout_names`];
+    /// phonetic unless set.
+    pub fn set_layout_name(&mut self, name: &str) {
+        self.layout = name.to_string();
+    }
+
+    pub fn layout_name(&self) -> &str {
+        &self.layout
+    }
+
+    /// `text` converted with the current layout.
+    pub fn convert(&self, text: &str) -> String {
+        self.keyboard.convert_text(text)
+    }
+
+    /// Does what a hotkey is bound to.
+    fn hotkey(&mut self, action: HotkeyAction) -> Vec<Action> {
+        let mut actions = Actions::default();
+        match action {
+            HotkeyAction::Toggle => self.state.toggle(),
+            HotkeyAction::ConvertSelection => {
+                self.state.reset_word();
+                actions.0.push(Action::ConvertSelection);
+            }
+            HotkeyAction::ForceEnglish => {
+                self.state
+                    .force_english_word(&self.keyboard, &self.history, &mut actions)
+            }
+            HotkeyAction::SwitchLayout => {
+                let next = config::next_layout(&self.layout);
+                match config::load_keyboard(next) {
+                    Ok(keyboard) => {
+                        self.keyboard = keyboard;
+                        self.layout = next.to_string();
+                        self.state.reset_word();
+                    }
+                    Err(e) => eprintln!("bengali-x11: {e}"),
+                }
+            }
+        }
+        actions.0
+    }
+
+    /// Handles a key press or release with `modifiers` (the event's state)
+    /// held, returning what to do instead. A key that is neither forwarded
+    /// nor typed is swallowed.
+    pub fn key(&mut self, keymap: &Keymap, keycode: u8, modifiers: u16, press: bool) -> Vec<Action> {
+        let forward = vec![Action::Forward { keycode, press }];
+        if let Some(key) = Key::from_keysym(keymap.keysym(keycode, 0)) {
+            let held = [
+                (CONTROL_MASK, hotkey::CTRL),
+                (MOD1_MASK, hotkey::ALT),
+                (SHIFT_MASK, hotkey::SHIFT),
+                (MOD4_MASK, hotkey::SUPER),
+            ]
+            .into_iter()
+            .filter(|&(mask, _)| modifiers & mask != 0)
+            .fold(0, |held, (_, bit)| held | bit);
+            if let Some(action) = self.hotkeys.key(key, held, press, Instant::now()) {
+                let mut actions = self.hotkey(action);
+                if press {
+                    self.swallowed.insert(keycode);
+                } else {
+                    // A tapped modifier's press went through
+                    actions.extend(forward);
+                }
+                return actions;
+            }
+        }
+        if !press {
+            return if self.swallowed.remove(&keycode) {
+                Vec::new()
+            } else {
+                forward
+            };
+        }
+
+        let keysym = keymap.keysym(keycode, modifiers);
+        if is_modifier_keysym(keysym) {
+            return forward;
+        }
+
+        // Ctrl, Alt and Super shortcuts go to the application and end the word
+        if modifiers & (CONTROL_MASK | MOD1_MASK | MOD4_MASK) != 0 {
+            self.state.reset_word();
+            return forward;
+        }
+        let Some(ch) = keysym_to_char(keysym) else {
+            // Arrows, Home and the like move away from the word
+            self.state.reset_word();
+            return forward;
+        };
+
+        let mut actions = Actions::default();
+        let altgr = modifiers & MOD5_MASK != 0;
+        let suppressed = self
+            .state
+            .process_key(&self.keyboard, &self.history, ch, altgr, &mut actions);
+        if suppressed {
+            self.swallowed.insert(keycode);
+        } else {
+            actions.0.extend(forward);
+        }
+        actions.0
+    }
+}
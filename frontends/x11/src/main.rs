@@ -0,0 +1,57 @@
+//! `bengali-x11`: types Bengali into any X11 application by grabbing the
+//! keyboard and typing the converted text with XTest.
+
+use bengali_keyboard::{config, History};
+use bengali_keyboard_x11::{session, Translator};
+use std::env;
+use std::process;
+
+const USAGE: &str = "\
+Usage: bengali-x11 [OPTIONS]
+
+Types Bengali into X11 applications, converting as you type like the
+Windows tray application. F10 toggles conversion, unless hotkeys.toml in
+the config directory says otherwise.
+
+Options:
+  -l, --layout LAYOUT  phonetic (default), bijoy, national or probhat
+  -h, --help           Show this help";
+
+fn parse_args() -> Result<String, String> {
+    let mut layout = "phonetic".to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "-l" | "--layout" => layout = args.next().ok_or_else(|| format!("{arg} needs a value"))?,
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    Ok(layout)
+}
+
+fn run(layout: String) -> Result<(), String> {
+    let keyboard = config::load_keyboard(&layout).map_err(|e| e.to_string())?;
+    let hotkeys = config::load_hotkeys().map_err(|e| e.to_string())?;
+    let history = config::load_history().unwrap_or_else(|e| {
+        eprintln!("bengali-x11: {e}, ignoring the history");
+        History::new()
+    });
+    let (conn, screen) = x11rb::connect(None).map_err(|e| e.to_string())?;
+    let mut translator = Translator::new(keyboard);
+    translator.set_layout_name(&layout);
+    translator.set_hotkeys(hotkeys);
+    translator.set_history(history);
+    session::run(&conn, screen, &mut translator).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(message) = result {
+        eprintln!("bengali-x11: {message}");
+        process::exit(1);
+    }
+}
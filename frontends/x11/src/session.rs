@@ -0,0 +1,231 @@
+//! The X server side: grabbing the keyboard and typing through XTest.
+
+use crate::keymap::{self, Keymap, NO_SYMBOL};
+use crate::{Action, Translator};
+use bengali_keyboard::keysym::KEY_BACKSPACE;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    AtomEnum, ConnectionExt as _, CreateWindowAux, GrabMode, GrabStatus, Keycode, Window, WindowClass, KEY_PRESS_EVENT,
+    KEY_RELEASE_EVENT,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::protocol::Event;
+use x11rb::CURRENT_TIME;
+
+/// Reads the server's current key mapping.
+pub fn load_keymap(conn: &impl Connection) -> Result<Keymap, Box<dyn Error>> {
+    let setup = conn.setup();
+    let count = setup.max_keycode - setup.min_keycode + 1;
+    let reply = conn.get_keyboard_mapping(setup.min_keycode, count)?.reply()?;
+    Ok(Keymap::new(setup.min_keycode, reply.keysyms_per_keycode, reply.keysyms))
+}
+
+/// Types on the focused window with XTest.
+pub struct Typist<'c, C: Connection> {
+    conn: &'c C,
+    root: Window,
+    /// Unused key codes lent to the characters we type, and what each one
+    /// carries now. The X keymap changes only when a new character comes
+    /// along, so applications see few MappingNotify events.
+    spare: Vec<(Keycode, Option<char>)>,
+    /// The spare key code to reuse next.
+    next_spare: usize,
+}
+
+impl<'c, C: Connection> Typist<'c, C> {
+    pub fn new(conn: &'c C, root: Window, keymap: &Keymap) -> Self {
+        let mut spare: Vec<(Keycode, Option<char>)> =
+            keymap.unused_keycodes().into_iter().map(|code| (code, None)).collect();
+        if spare.is_empty() {
+            // Borrow the highest key code, which few keyboards have
+            spare.push((conn.setup().max_keycode, None));
+        }
+        Self {
+            conn,
+            root,
+            spare,
+            next_spare: 0,
+        }
+    }
+
+    fn fake(&self, keycode: Keycode, press: bool) -> Result<(), Box<dyn Error>> {
+        let kind = if press { KEY_PRESS_EVENT } else { KEY_RELEASE_EVENT };
+        self.conn
+            .xtest_fake_input(kind, keycode, CURRENT_TIME, self.root, 0, 0, 0)?;
+        Ok(())
+    }
+
+    fn tap(&self, keycode: Keycode) -> Result<(), Box<dyn Error>> {
+        self.fake(keycode, true)?;
+        self.fake(keycode, false)
+    }
+
+    /// A key code that types `ch`, remapping a spare one if need be.
+    fn keycode_for(&mut self, ch: char) -> Result<Keycode, Box<dyn Error>> {
+        if let Some(&(keycode, _)) = self.spare.iter().find(|(_, carried)| *carried == Some(ch)) {
+            return Ok(keycode);
+        }
+        let index = self.next_spare;
+        self.next_spare = (index + 1) % self.spare.len();
+
+        // The same keysym on both levels, so a held Shift changes nothing
+        let keycode = self.spare[index].0;
+        let keysym = keymap::char_to_keysym(ch);
+        self.conn.change_keyboard_mapping(1, keycode, 2, &[keysym, keysym])?;
+        self.spare[index].1 = Some(ch);
+        Ok(keycode)
+    }
+
+    /// Carries out `actions` on the focused window.
+    pub fn perform(&mut self, keymap: &Keymap, actions: &[Action]) -> Result<(), Box<dyn Error>> {
+        for action in actions {
+            match action {
+                Action::Forward { keycode, press } => self.fake(*keycode, *press)?,
+                Action::Backspaces(count) => {
+                    let (backspace, _) = keymap
+                        .keycode(KEY_BACKSPACE)
+                        .ok_or("no BackSpace key in the keymap")?;
+                    for _ in 0..*count {
+                        self.tap(backspace)?;
+                    }
+                }
+                Action::Text(text) => {
+                    for ch in text.chars() {
+                        let keycode = self.keycode_for(ch)?;
+                        self.tap(keycode)?;
+                    }
+                }
+                // run reads the selection and passes its conversion as text
+                Action::ConvertSelection => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts the spare key codes back the way they were.
+    pub fn restore(&self) -> Result<(), Box<dyn Error>> {
+        for &(keycode, carried) in &self.spare {
+            if carried.is_some() {
+                self.conn
+                    .change_keyboard_mapping(1, keycode, 2, &[NO_SYMBOL, NO_SYMBOL])?;
+            }
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+/// Grabs the keyboard, waiting a little for another client's grab (an open
+/// menu, say) to end.
+fn grab_keyboard(conn: &impl Connection, root: Window) -> Result<(), Box<dyn Error>> {
+    for _ in 0..100 {
+        let reply = conn
+            .grab_keyboard(false, root, CURRENT_TIME, GrabMode::ASYNC, GrabMode::ASYNC)?
+            .reply()?;
+        if reply.status == GrabStatus::SUCCESS {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    Err("could not grab the keyboard".into())
+}
+
+/// Reads the PRIMARY selection as UTF-8 into a property of `window`. Events
+/// that arrive meanwhile are kept in `pending`.
+fn read_selection(
+    conn: &impl Connection,
+    window: Window,
+    pending: &mut VecDeque<Event>,
+) -> Result<String, Box<dyn Error>> {
+    let utf8 = conn.intern_atom(false, b"UTF8_STRING")?.reply()?.atom;
+    let property = conn.intern_atom(false, b"BENGALI_SELECTION")?.reply()?.atom;
+    conn.convert_selection(window, AtomEnum::PRIMARY.into(), utf8, property, CURRENT_TIME)?;
+    conn.flush()?;
+    loop {
+        match conn.wait_for_event()? {
+            Event::SelectionNotify(notify) if notify.requestor == window => {
+                // The property is None when there is no selection
+                if notify.property == u32::from(AtomEnum::NONE) {
+                    return Ok(String::new());
+                }
+                break;
+            }
+            event => pending.push_back(event),
+        }
+    }
+    let reply = conn
+        .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)?
+        .reply()?;
+    Ok(String::from_utf8_lossy(&reply.value).into_owned())
+}
+
+/// Converts everything typed on `screen` until the connection fails.
+///
+/// Our own XTest events would come straight back to us while we hold the
+/// keyboard, so it is let go while they are sent, much as the Win32 hook
+/// skips injected events.
+pub fn run(conn: &impl Connection, screen: usize, translator: &mut Translator) -> Result<(), Box<dyn Error>> {
+    let root = conn.setup().roots.get(screen).ok_or("no such screen")?.root;
+    conn.xtest_get_version(2, 2)?.reply()?;
+    let mut keymap = load_keymap(conn)?;
+    let mut typist = Typist::new(conn, root, &keymap);
+    // An unmapped window to receive the selection on
+    let window = conn.generate_id()?;
+    conn.create_window(
+        0,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        0,
+        &CreateWindowAux::new(),
+    )?;
+    grab_keyboard(conn, root)?;
+
+    let mut pending = VecDeque::new();
+    let result = loop {
+        let event = match pending.pop_front().map(Ok).unwrap_or_else(|| conn.wait_for_event()) {
+            Ok(event) => event,
+            Err(e) => break Err(e.into()),
+        };
+        let actions = match event {
+            Event::KeyPress(key) => translator.key(&keymap, key.detail, u16::from(key.state), true),
+            Event::KeyRelease(key) => translator.key(&keymap, key.detail, u16::from(key.state), false),
+            Event::MappingNotify(_) => {
+                keymap = load_keymap(conn)?;
+                continue;
+            }
+            _ => continue,
+        };
+        if actions.is_empty() {
+            continue;
+        }
+        let mut resolved = Vec::with_capacity(actions.len());
+        for action in actions {
+            resolved.push(match action {
+                Action::ConvertSelection => {
+                    Action::Text(translator.convert(&read_selection(conn, window, &mut pending)?))
+                }
+                action => action,
+            });
+        }
+        let actions = resolved;
+
+        conn.ungrab_keyboard(CURRENT_TIME)?;
+        typist.perform(&keymap, &actions)?;
+        // Wait for the server to deliver our events before taking the
+        // keyboard back
+        conn.get_input_focus()?.reply()?;
+        grab_keyboard(conn, root)?;
+    };
+    let _ = typist.restore();
+    result
+}
@@ -0,0 +1,455 @@
+//! Runs key events through the translator against a US keymap and reads
+//! what it types back. The round trip through a real X server needs Xvfb,
+//! so it is ignored unless asked for with
+//! `cargo test -p bengali_keyboard_x11 -- --ignored`.
+
+use bengali_keyboard::keysym::{KEY_BACKSPACE, KEY_F10};
+use bengali_keyboard::{BengaliKeyboard, FixedLayout, History, Hotkeys, BACKSPACE};
+use bengali_keyboard_x11::keymap::{self, Keymap, CONTROL_MASK, MOD1_MASK, MOD5_MASK, NO_SYMBOL, SHIFT_MASK};
+use bengali_keyboard_x11::{session, Action, Translator};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    ConnectionExt as _, CreateWindowAux, EventMask, GrabMode, GrabStatus, InputFocus, WindowClass, KEY_PRESS_EVENT,
+    KEY_RELEASE_EVENT,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::protocol::Event;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME};
+
+const SHIFT_L: u8 = 50;
+const CONTROL_L: u8 = 37;
+const ISO_LEVEL3_SHIFT: u8 = 108;
+const F10: u8 = 76;
+const SPACE: u8 = 65;
+
+/// The usual XFree86 key codes for a US keyboard, two levels each.
+fn us_keymap() -> Keymap {
+    let mut keys: Vec<(u8, u32, u32)> = vec![
+        (9, 0xff1b, NO_SYMBOL),
+        (22, KEY_BACKSPACE, NO_SYMBOL),
+        (23, 0xff09, NO_SYMBOL),
+        (36, 0xff0d, NO_SYMBOL),
+        (CONTROL_L, 0xffe3, NO_SYMBOL),
+        (SHIFT_L, 0xffe1, NO_SYMBOL),
+        (SPACE, u32::from(' '), NO_SYMBOL),
+        (F10, KEY_F10, NO_SYMBOL),
+        (ISO_LEVEL3_SHIFT, 0xfe03, NO_SYMBOL),
+        (113, 0xff51, NO_SYMBOL),
+    ];
+    let rows = [
+        (10, "1234567890-=", "!@#$%^&*()_+"),
+        (24, "qwertyuiop[]", "QWERTYUIOP{}"),
+        (38, "asdfghjkl;'`", "ASDFGHJKL:\"~"),
+        (51, "\\zxcvbnm,./", "|ZXCVBNM<>?"),
+    ];
+    for (first, lower, upper) in rows {
+        for (offset, (lower, upper)) in lower.chars().zip(upper.chars()).enumerate() {
+            keys.push((first + offset as u8, u32::from(lower), u32::from(upper)));
+        }
+    }
+    let mut keysyms = vec![NO_SYMBOL; (256 - 8) * 2];
+    for (keycode, lower, upper) in keys {
+        let index = usize::from(keycode - 8) * 2;
+        keysyms[index] = lower;
+        keysyms[index + 1] = upper;
+    }
+    Keymap::new(8, 2, keysyms)
+}
+
+/// Key presses and releases typing `text`, with Shift held for shifted
+/// characters.
+fn typing(keymap: &Keymap, text: &str) -> Vec<(u8, bool)> {
+    let mut events = Vec::new();
+    for ch in text.chars() {
+        let keysym = if ch == BACKSPACE { KEY_BACKSPACE } else { u32::from(ch) };
+        let (keycode, shift) = keymap.keycode(keysym).expect("typeable");
+        if shift {
+            events.push((SHIFT_L, true));
+        }
+        events.extend([(keycode, true), (keycode, false)]);
+        if shift {
+            events.push((SHIFT_L, false));
+        }
+    }
+    events
+}
+
+/// A text field reading forwarded keys and typed text.
+#[derive(Default)]
+struct Screen {
+    text: String,
+    /// Keys pressed with Ctrl.
+    shortcuts: Vec<char>,
+    modifiers: u16,
+}
+
+impl Screen {
+    fn apply(&mut self, keymap: &Keymap, actions: &[Action]) {
+        for action in actions {
+            match action {
+                Action::Forward { keycode, press } => self.key(keymap, *keycode, *press),
+                Action::Backspaces(count) => {
+                    for _ in 0..*count {
+                        self.text.pop();
+                    }
+                }
+                Action::Text(text) => self.text.push_str(text),
+                Action::ConvertSelection => panic!("nothing is selected"),
+            }
+        }
+    }
+
+    fn key(&mut self, keymap: &Keymap, keycode: u8, press: bool) {
+        let mask = match keycode {
+            SHIFT_L => SHIFT_MASK,
+            CONTROL_L => CONTROL_MASK,
+            ISO_LEVEL3_SHIFT => MOD5_MASK,
+            _ => 0,
+        };
+        if mask != 0 {
+            if press {
+                self.modifiers |= mask;
+            } else {
+                self.modifiers &= !mask;
+            }
+            return;
+        }
+        if !press {
+            return;
+        }
+        let keysym = keymap.keysym(keycode, self.modifiers);
+        if keysym == KEY_BACKSPACE {
+            self.text.pop();
+        } else if let Some(ch) = char::from_u32(keysym).filter(|_| keysym < 0x100) {
+            if self.modifiers & CONTROL_MASK != 0 {
+                self.shortcuts.push(ch);
+            } else {
+                self.text.push(ch);
+            }
+        }
+    }
+}
+
+/// Feeds `events` to `translator` the way the session does, tracking the
+/// modifier state X would report.
+fn translate(translator: &mut Translator, keymap: &Keymap, events: &[(u8, bool)]) -> Screen {
+    let mut screen = Screen::default();
+    for &(keycode, press) in events {
+        let actions = translator.key(keymap, keycode, screen.modifiers, press);
+        screen.apply(keymap, &actions);
+    }
+    screen
+}
+
+fn phonetic() -> Translator {
+    Translator::new(BengaliKeyboard::new())
+}
+
+#[test]
+fn keymap_levels_follow_shift_and_caps_lock() {
+    let keymap = us_keymap();
+    assert_eq!(keymap.keysym(38, 0), u32::from('a'));
+    assert_eq!(keymap.keysym(38, SHIFT_MASK), u32::from('A'));
+    assert_eq!(keymap.keysym(38, keymap::LOCK_MASK), u32::from('A'));
+    assert_eq!(keymap.keysym(38, SHIFT_MASK | keymap::LOCK_MASK), u32::from('a'));
+    assert_eq!(keymap.keysym(10, keymap::LOCK_MASK), u32::from('1'));
+    assert_eq!(keymap.keycode(u32::from('"')), Some((48, true)));
+    assert!(keymap.unused_keycodes().contains(&255));
+    assert!(!keymap.unused_keycodes().contains(&SPACE));
+    assert_eq!(keymap::char_to_keysym('a'), 0x61);
+    assert_eq!(keymap::char_to_keysym('আ'), 0x0100_0986);
+}
+
+#[test]
+fn words_are_converted_as_typed() {
+    let keymap = us_keymap();
+    let mut translator = phonetic();
+    let events = typing(&keymap, "amar bangla ");
+    let screen = translate(&mut translator, &keymap, &events);
+    assert_eq!(screen.text, "আমার বাংলা ");
+
+    // The roman letters themselves never reach the application; the space
+    // that ends a word does
+    let mut translator = phonetic();
+    let forwarded: Vec<_> = events
+        .iter()
+        .flat_map(|&(keycode, press)| translator.key(&keymap, keycode, 0, press))
+        .filter(|action| matches!(action, Action::Forward { keycode, .. } if *keycode != SPACE))
+        .collect();
+    assert!(forwarded.is_empty(), "{forwarded:?}");
+}
+
+#[test]
+fn words_use_the_history() {
+    let keymap = us_keymap();
+    let mut history = History::new();
+    history.record("ami", "আমিই");
+    let mut translator = phonetic();
+    translator.set_history(history);
+    let screen = translate(&mut translator, &keymap, &typing(&keymap, "ami kor "));
+    assert_eq!(screen.text, "আমিই কর ");
+
+    // Including the word the force-English hotkey finishes
+    translator.set_hotkeys(Hotkeys::from_toml("force_english = \"Shift Shift\"").unwrap());
+    let mut events = typing(&keymap, "ami");
+    events.extend([(SHIFT_L, true), (SHIFT_L, false), (SHIFT_L, true), (SHIFT_L, false)]);
+    events.extend(typing(&keymap, " ami"));
+    assert_eq!(translate(&mut translator, &keymap, &events).text, "আমিই ami");
+}
+
+#[test]
+fn backspace_erases_the_converted_word() {
+    let keymap = us_keymap();
+    let screen = translate(
+        &mut phonetic(),
+        &keymap,
+        &typing(&keymap, "kor\x08\x08\x08ami\x08\x08\x08\x08"),
+    );
+    assert_eq!(screen.text, "");
+}
+
+#[test]
+fn shift_held_types_the_upper_level() {
+    let keymap = us_keymap();
+    let mut events = vec![(SHIFT_L, true)];
+    events.extend(typing(&keymap, "bha"));
+    events.push((SHIFT_L, false));
+    let screen = translate(&mut phonetic(), &keymap, &events);
+    assert_eq!(screen.text, BengaliKeyboard::new().convert_text("BHA"));
+}
+
+#[test]
+fn shortcuts_pass_through_and_end_the_word() {
+    let keymap = us_keymap();
+    let mut events = typing(&keymap, "am");
+    events.extend([(CONTROL_L, true), (54, true), (54, false), (CONTROL_L, false)]);
+    events.extend(typing(&keymap, "i "));
+    let screen = translate(&mut phonetic(), &keymap, &events);
+    assert_eq!(screen.text, "আমই ");
+    assert_eq!(screen.shortcuts, ['c']);
+}
+
+#[test]
+fn f10_toggles_conversion() {
+    let keymap = us_keymap();
+    let mut translator = phonetic();
+    assert!(translator.key(&keymap, F10, 0, true).is_empty());
+    assert!(translator.key(&keymap, F10, 0, false).is_empty());
+    assert!(!translator.is_enabled());
+    let screen = translate(&mut translator, &keymap, &typing(&keymap, "ami "));
+    assert_eq!(screen.text, "ami ");
+}
+
+#[test]
+fn configured_hotkeys_replace_f10() {
+    let keymap = us_keymap();
+    let mut translator = phonetic();
+    translator.set_hotkeys(
+        Hotkeys::from_toml(
+            "toggle = \"Ctrl+Space\"\nconvert_selection = \"Ctrl+Alt+b\"\nforce_english = \"Shift Shift\"",
+        )
+        .unwrap(),
+    );
+    // F10 is an ordinary key now
+    assert_eq!(
+        translator.key(&keymap, F10, 0, true),
+        [Action::Forward {
+            keycode: F10,
+            press: true
+        }]
+    );
+    translator.key(&keymap, F10, 0, false);
+
+    // The completing key is kept from the application; Ctrl is not
+    assert!(translator.key(&keymap, SPACE, CONTROL_MASK, true).is_empty());
+    assert!(translator.key(&keymap, SPACE, CONTROL_MASK, false).is_empty());
+    assert!(!translator.is_enabled());
+    assert!(translator.key(&keymap, SPACE, CONTROL_MASK, true).is_empty());
+    assert!(translator.is_enabled());
+    translator.key(&keymap, SPACE, CONTROL_MASK, false);
+
+    let (b, _) = keymap.keycode(u32::from('b')).unwrap();
+    assert_eq!(
+        translator.key(&keymap, b, CONTROL_MASK | MOD1_MASK, true),
+        [Action::ConvertSelection]
+    );
+    translator.key(&keymap, b, CONTROL_MASK | MOD1_MASK, false);
+
+    // Shift tapped twice types the next word in English
+    let mut events = typing(&keymap, "ami ");
+    events.extend([(SHIFT_L, true), (SHIFT_L, false), (SHIFT_L, true), (SHIFT_L, false)]);
+    events.extend(typing(&keymap, "email ami"));
+    assert_eq!(translate(&mut translator, &keymap, &events).text, "আমি email আমি");
+}
+
+#[test]
+fn altgr_types_the_fixed_layout_level() {
+    let keymap = us_keymap();
+    let mut translator = Translator::new(BengaliKeyboard::with_fixed_layout(
+        FixedLayout::builtin("national").unwrap(),
+    ));
+    let mut events = vec![(ISO_LEVEL3_SHIFT, true)];
+    events.extend(typing(&keymap, "f"));
+    events.push((ISO_LEVEL3_SHIFT, false));
+    assert_eq!(translate(&mut translator, &keymap, &events).text, "আ");
+}
+
+/// An Xvfb server, killed when dropped.
+struct Xvfb {
+    child: Child,
+    display: String,
+}
+
+impl Xvfb {
+    fn start() -> Self {
+        let display = format!(":{}", 90 + std::process::id() % 100);
+        let child = Command::new("Xvfb")
+            .args([&display, "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Xvfb not found");
+        let xvfb = Self { child, display };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while x11rb::connect(Some(&xvfb.display)).is_err() {
+            assert!(Instant::now() < deadline, "Xvfb did not start");
+            thread::sleep(Duration::from_millis(50));
+        }
+        xvfb
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A focused window that keeps what is typed into it.
+struct TestClient<C: Connection> {
+    conn: C,
+    keymap: Keymap,
+    text: String,
+}
+
+impl<C: Connection> TestClient<C> {
+    fn new(conn: C, screen: usize) -> Self {
+        let root = conn.setup().roots[screen].root;
+        let window = conn.generate_id().unwrap();
+        let aux = CreateWindowAux::new().event_mask(EventMask::KEY_PRESS);
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            200,
+            50,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &aux,
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        conn.set_input_focus(InputFocus::POINTER_ROOT, window, CURRENT_TIME)
+            .unwrap();
+        conn.sync().unwrap();
+        let keymap = session::load_keymap(&conn).unwrap();
+        Self {
+            conn,
+            keymap,
+            text: String::new(),
+        }
+    }
+
+    /// Reads key events until the text is `expected`.
+    fn wait_for(&mut self, expected: &str) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while self.text != expected {
+            assert!(
+                Instant::now() < deadline,
+                "typed {:?}, expected {expected:?}",
+                self.text
+            );
+            match self.conn.poll_for_event().unwrap() {
+                Some(Event::MappingNotify(_)) => self.keymap = session::load_keymap(&self.conn).unwrap(),
+                Some(Event::KeyPress(key)) => {
+                    let keysym = self.keymap.keysym(key.detail, u16::from(key.state));
+                    match keysym {
+                        KEY_BACKSPACE => drop(self.text.pop()),
+                        0x0100_0000.. => self.text.extend(char::from_u32(keysym - 0x0100_0000)),
+                        _ => self.text.extend(char::from_u32(keysym).filter(|_| keysym < 0x100)),
+                    }
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+}
+
+/// Waits until someone else, the session, holds the keyboard.
+fn wait_for_grab(conn: &impl Connection, root: u32) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let reply = conn
+            .grab_keyboard(false, root, CURRENT_TIME, GrabMode::ASYNC, GrabMode::ASYNC)
+            .unwrap()
+            .reply()
+            .unwrap();
+        if reply.status == GrabStatus::ALREADY_GRABBED {
+            return;
+        }
+        if reply.status == GrabStatus::SUCCESS {
+            conn.ungrab_keyboard(CURRENT_TIME).unwrap();
+            conn.sync().unwrap();
+        }
+        assert!(Instant::now() < deadline, "the session never grabbed the keyboard");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn types_into_an_x_client() {
+    let xvfb = Xvfb::start();
+
+    let (conn, screen) = x11rb::connect(Some(&xvfb.display)).unwrap();
+    let mut client = TestClient::new(conn, screen);
+
+    let display = xvfb.display.clone();
+    thread::spawn(move || {
+        let (conn, screen) = x11rb::connect(Some(&display)).unwrap();
+        // Ends when Xvfb goes away
+        let _ = session::run(&conn, screen, &mut phonetic());
+    });
+
+    // Type a key at a time, each once the session has the keyboard again,
+    // so none slips past it while it is typing
+    let (typist, screen) = x11rb::connect(Some(&xvfb.display)).unwrap();
+    let root = typist.setup().roots[screen].root;
+    let keymap = session::load_keymap(&typist).unwrap();
+    let text = "amar bangla ";
+    let converter = BengaliKeyboard::new();
+    for (index, ch) in text.char_indices() {
+        wait_for_grab(&typist, root);
+        let (keycode, _) = keymap.keycode(u32::from(ch)).unwrap();
+        typist
+            .xtest_fake_input(KEY_PRESS_EVENT, keycode, CURRENT_TIME, root, 0, 0, 0)
+            .unwrap();
+        typist
+            .xtest_fake_input(KEY_RELEASE_EVENT, keycode, CURRENT_TIME, root, 0, 0, 0)
+            .unwrap();
+        typist.sync().unwrap();
+        client.wait_for(&converter.convert_text(&text[..=index]));
+        // A forwarded release is sent after the text shows up
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(client.text, "আমার বাংলা ");
+}
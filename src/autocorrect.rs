@@ -0,0 +1,87 @@
+//! Whole-word corrections applied when a word is finished.
+//!
+//! An autocorrect file has a `[words]` table matched exactly as typed and
+//! an `[any_case]` table matched in any case, both mapping a typed word to
+//! the Bengali that replaces it. `dictionary/autocorrect.toml` is the
+//! bundled list and documents the format.
+
+use crate::layout::LayoutError;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// The bundled corrections, used by [`Autocorrect::builtin`].
+pub const DEFAULT_AUTOCORRECT: &str = include_str!("../dictionary/autocorrect.toml");
+
+#[derive(Debug, Default, Clone)]
+pub struct Autocorrect {
+    words: HashMap<String, String>,
+    /// Keyed by the lower-case word.
+    any_case: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AutocorrectFile {
+    #[serde(default)]
+    words: BTreeMap<String, String>,
+    #[serde(default)]
+    any_case: BTreeMap<String, String>,
+}
+
+fn check_entries(table: BTreeMap<String, String>, section: &str) -> Result<HashMap<String, String>, LayoutError> {
+    for (word, replacement) in &table {
+        if word.is_empty() || word.chars().any(char::is_whitespace) {
+            return Err(LayoutError::Invalid(format!("{section}.{word:?} is not a single word")));
+        }
+        if replacement.is_empty() {
+            return Err(LayoutError::Invalid(format!("{section}.{word:?} has no replacement")));
+        }
+    }
+    Ok(table.into_iter().collect())
+}
+
+impl Autocorrect {
+    /// No corrections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bundled corrections.
+    pub fn builtin() -> Self {
+        Self::from_toml(DEFAULT_AUTOCORRECT).expect("built-in autocorrect is valid")
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, LayoutError> {
+        let file: AutocorrectFile = toml::from_str(source).map_err(LayoutError::Parse)?;
+        let any_case = check_entries(file.any_case, "any_case")?;
+        if let Some(word) = any_case.keys().find(|word| word.to_lowercase() != **word) {
+            return Err(LayoutError::Invalid(format!("any_case.{word:?} must be in lower case")));
+        }
+        Ok(Self { words: check_entries(file.words, "words")?, any_case })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LayoutError::Io(path.to_path_buf(), e))?;
+        Self::from_toml(&source)
+    }
+
+    /// The replacement for a finished `word`: an exact entry, otherwise one
+    /// that matches in any case.
+    pub fn correct(&self, word: &str) -> Option<&str> {
+        self.words
+            .get(word)
+            .or_else(|| self.any_case.get(&word.to_lowercase()))
+            .map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len() + self.any_case.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.any_case.is_empty()
+    }
+}
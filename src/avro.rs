@@ -0,0 +1,332 @@
+//! Importers for OmicronLab Avro Keyboard layouts.
+//!
+//! Two formats are understood:
+//!
+//! * `.avrolayout`, the XML format of Avro's fixed layouts. Every key of the
+//!   `Normal` and `Shift` levels becomes a pattern for the character that key
+//!   types on a US keyboard, with the key's text as verbatim output.
+//! * The Avro Phonetic grammar in JSON: `patterns` with `find`/`replace` and
+//!   optional `rules`, each rule a list of prefix/suffix `matches` on the
+//!   `vowel`, `consonant`, `punctuation` or `exact` scopes.
+//!
+//! Avro tests its rules against the roman input while [`ContextRule`]s test
+//! the class of the Bengali output, so `consonant` and `vowel` scopes carry
+//! over directly and `punctuation` becomes a word boundary. What has no
+//! counterpart here (`exact` matches, negated vowel and punctuation scopes,
+//! AltGr levels) is left out and listed in the [`ImportReport`].
+
+use crate::escape::Escapes;
+use crate::keymap::{Context, ContextRule, KeyMap};
+use crate::script;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Constructs of an imported layout that could not be translated, one
+/// human-readable line each.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub untranslated: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn is_empty(&self) -> bool {
+        self.untranslated.is_empty()
+    }
+
+    fn note(&mut self, message: String) {
+        self.untranslated.push(message);
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(PathBuf, std::io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ImportError::Xml(e) => write!(f, "invalid .avrolayout file: {e}"),
+            ImportError::Json(e) => write!(f, "invalid Avro Phonetic grammar: {e}"),
+            ImportError::Invalid(message) => write!(f, "invalid Avro layout: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(_, e) => Some(e),
+            ImportError::Xml(e) => Some(e),
+            ImportError::Json(e) => Some(e),
+            ImportError::Invalid(_) => None,
+        }
+    }
+}
+
+/// Imports a file by its extension: `.avrolayout` or `.json`.
+pub fn import_file(path: impl AsRef<Path>) -> Result<(KeyMap, ImportReport), ImportError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| ImportError::Io(path.to_path_buf(), e))?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("avrolayout") => import_avrolayout(&source),
+        Some("json") => import_phonetic(&source),
+        _ => Err(ImportError::Invalid(format!(
+            "{}: expected a .avrolayout or .json file",
+            path.display()
+        ))),
+    }
+}
+
+/// The unshifted and shifted character of a key on a US keyboard, by the
+/// name `.avrolayout` files give it.
+fn key_chars(key: &str) -> Option<(char, char)> {
+    const DIGITS_SHIFTED: &str = ")!@#$%^&*(";
+
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c {
+            'A'..='Z' => Some((c.to_ascii_lowercase(), c)),
+            '0'..='9' => Some((c, DIGITS_SHIFTED.chars().nth(c as usize - '0' as usize)?)),
+            _ => None,
+        };
+    }
+
+    Some(match key.replace('_', "").as_str() {
+        "OEM1" => (';', ':'),
+        "OEM2" => ('/', '?'),
+        "OEM3" => ('`', '~'),
+        "OEM4" => ('[', '{'),
+        "OEM5" => ('\\', '|'),
+        "OEM6" => (']', '}'),
+        "OEM7" => ('\'', '"'),
+        "OEMMINUS" => ('-', '_'),
+        "OEMPLUS" => ('=', '+'),
+        "OEMCOMMA" => (',', '<'),
+        "OEMPERIOD" => ('.', '>'),
+        _ => return None,
+    })
+}
+
+/// Imports an Avro Keyboard fixed layout (`.avrolayout` XML).
+///
+/// The keymap has one single-character pattern per key and does not join
+/// consonants, so it types exactly what the layout says.
+pub fn import_avrolayout(xml: &str) -> Result<(KeyMap, ImportReport), ImportError> {
+    let document = roxmltree::Document::parse(xml).map_err(ImportError::Xml)?;
+    let root = document.root_element();
+    let name = root
+        .children()
+        .find(|node| node.has_tag_name("LayoutName"))
+        .and_then(|node| node.text())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Avro layout")
+        .to_string();
+    let key_data = root
+        .children()
+        .find(|node| node.has_tag_name("KeyData"))
+        .ok_or_else(|| ImportError::Invalid("no <KeyData> element".to_string()))?;
+
+    let mut patterns = HashMap::new();
+    let mut report = ImportReport::default();
+    for node in key_data.children().filter(|node| node.is_element()) {
+        let tag = node.tag_name().name();
+        let Some(output) = node.text().filter(|text| !text.is_empty()) else {
+            continue;
+        };
+
+        let parsed = tag
+            .strip_prefix("Key_")
+            .and_then(|rest| rest.rsplit_once('_'))
+            .and_then(|(key, level)| Some((key_chars(key)?, level)));
+        let ch = match parsed {
+            Some(((normal, _), "Normal")) => normal,
+            Some(((_, shifted), "Shift")) => shifted,
+            Some((_, level @ ("AltGr" | "ShiftAltGr"))) => {
+                report.note(format!("{tag} = {output:?}: the {level} level is not supported"));
+                continue;
+            }
+            _ => {
+                report.note(format!("{tag} = {output:?}: unknown key"));
+                continue;
+            }
+        };
+        patterns.insert(ch.to_string(), script::classify(output));
+    }
+
+    let keymap = KeyMap {
+        name,
+        patterns,
+        vowel_diacritics: HashMap::new(),
+        rules: Vec::new(),
+        join_consonants: false,
+        escapes: Escapes::default(),
+    };
+    Ok((keymap, report))
+}
+
+#[derive(Deserialize)]
+struct Grammar {
+    patterns: Vec<PatternEntry>,
+    #[serde(default)]
+    casesensitive: String,
+}
+
+#[derive(Deserialize)]
+struct PatternEntry {
+    find: String,
+    replace: String,
+    #[serde(default)]
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Deserialize)]
+struct RuleEntry {
+    matches: Vec<MatchEntry>,
+    replace: String,
+}
+
+#[derive(Deserialize)]
+struct MatchEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    scope: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    negative: bool,
+}
+
+impl MatchEntry {
+    fn describe(&self) -> String {
+        if self.value.is_empty() {
+            format!("{} {}", self.kind, self.scope)
+        } else {
+            format!("{} {} {:?}", self.kind, self.scope, self.value)
+        }
+    }
+}
+
+/// Narrows `current` to also satisfy `new`, or `None` if no single
+/// [`Context`] satisfies both.
+fn narrow(current: Context, new: Context) -> Option<Context> {
+    match (current, new) {
+        (Context::Any, other) | (other, Context::Any) => Some(other),
+        (a, b) if a == b => Some(a),
+        (Context::NotConsonant, other @ (Context::Vowel | Context::Boundary))
+        | (other @ (Context::Vowel | Context::Boundary), Context::NotConsonant) => Some(other),
+        _ => None,
+    }
+}
+
+/// Translates one Avro rule, noting what was dropped; `None` if the rule
+/// cannot be expressed at all.
+fn translate_rule(find: &str, index: usize, rule: &RuleEntry, report: &mut ImportReport) -> Option<ContextRule> {
+    let mut prev = Context::Any;
+    let mut next = Context::Any;
+    for m in &rule.matches {
+        let (negated, scope) = match m.scope.strip_prefix('!') {
+            Some(scope) => (!m.negative, scope),
+            None => (m.negative, m.scope.as_str()),
+        };
+        let context = match (negated, scope) {
+            (false, "consonant") => Context::Consonant,
+            (false, "vowel") => Context::Vowel,
+            (false, "punctuation") => Context::Boundary,
+            (true, "consonant") => Context::NotConsonant,
+            (true, "exact") => {
+                report.note(format!("{find:?} rule {}: {} ignored", index + 1, m.describe()));
+                continue;
+            }
+            _ => {
+                report.note(format!(
+                    "{find:?} rule {}: {} cannot be expressed; rule skipped",
+                    index + 1,
+                    m.describe()
+                ));
+                return None;
+            }
+        };
+        let side = match m.kind.as_str() {
+            "prefix" => &mut prev,
+            "suffix" => &mut next,
+            kind => {
+                report.note(format!("{find:?} rule {}: unknown match type {kind:?}; rule skipped", index + 1));
+                return None;
+            }
+        };
+        match narrow(*side, context) {
+            Some(narrowed) => *side = narrowed,
+            None => {
+                report.note(format!("{find:?} rule {}: contradictory {} matches; rule skipped", index + 1, m.kind));
+                return None;
+            }
+        }
+    }
+
+    Some(ContextRule {
+        pattern: find.to_string(),
+        prev,
+        next,
+        output: script::classify(&rule.replace),
+    })
+}
+
+/// Imports an Avro Phonetic grammar (JSON).
+///
+/// Avro spells out every conjunct and কার in its grammar, so the keymap does
+/// not join consonants. Avro also folds the case of letters outside
+/// `casesensitive`; the imported layout matches input exactly as typed.
+pub fn import_phonetic(json: &str) -> Result<(KeyMap, ImportReport), ImportError> {
+    let grammar: Grammar = serde_json::from_str(json).map_err(ImportError::Json)?;
+
+    let mut patterns = HashMap::new();
+    let mut rules = Vec::new();
+    let mut report = ImportReport::default();
+    for entry in &grammar.patterns {
+        let find = &entry.find;
+        if find.is_empty() {
+            return Err(ImportError::Invalid("pattern with an empty find".to_string()));
+        }
+        if find.chars().any(char::is_whitespace) {
+            report.note(format!("{find:?}: patterns with whitespace never match; skipped"));
+            continue;
+        }
+        if patterns.contains_key(find) {
+            report.note(format!("{find:?}: defined more than once; the first is used"));
+            continue;
+        }
+
+        patterns.insert(find.clone(), script::classify(&entry.replace));
+        rules.extend(
+            entry
+                .rules
+                .iter()
+                .enumerate()
+                .filter_map(|(index, rule)| translate_rule(find, index, rule, &mut report)),
+        );
+    }
+
+    let folded: String = ('a'..='z').filter(|c| !grammar.casesensitive.contains(*c)).collect();
+    if !folded.is_empty() {
+        report.note(format!("letters {folded:?} are not case-folded; type them in lower case"));
+    }
+
+    let keymap = KeyMap {
+        name: "Avro Phonetic".to_string(),
+        patterns,
+        vowel_diacritics: HashMap::new(),
+        rules,
+        join_consonants: false,
+        escapes: Escapes::default(),
+    };
+    Ok((keymap, report))
+}
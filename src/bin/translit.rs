@@ -0,0 +1,254 @@
+//! Headless transliteration: roman (Banglish) text in, Bengali out.
+//!
+//! Reads the named files (or stdin) line by line and writes the converted
+//! text to stdout, so it can sit in a pipeline over arbitrarily large input.
+//! `--reverse` goes the other way, from Bengali to the keys that type it, and
+//! `--romanize` from Bengali to ISO 15919 or ALA-LC. Words are written with
+//! the spellings picked from candidates, a history it also manages.
+
+use bengali_keyboard::romanize::{self, Scheme};
+use bengali_keyboard::{avro, config, BengaliKeyboard, Escapes, FixedLayout, History, KeyMap, OpenEscape};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "\
+Usage: bengali-translit [OPTIONS] [FILE]...
+
+Converts roman (Banglish) text to Bengali. Reads stdin when no FILE is
+given or FILE is -.
+
+Options:
+  -l, --layout LAYOUT    phonetic (default), with your own layout.toml and
+                         autocorrect.toml if you have them; bijoy, national
+                         or probhat for text typed on those fixed layouts;
+                         or the path of a layout file: .toml, or an Avro
+                         .avrolayout or Avro Phonetic .json to import
+  -p, --preserve DELIMS  Copy text between the two DELIMS characters
+                         unconverted, e.g. --preserve '{}', instead of the
+                         layout's own escape delimiters (`like this`)
+  -r, --reverse          Convert Bengali back to the roman keys that type it
+  -R, --romanize SCHEME  Romanize Bengali with iso15919 or ala-lc
+  -o, --output FILE      Write to FILE instead of stdout
+  -h, --help             Show this help
+
+History of spellings picked from candidates:
+  --export-history FILE  Copy the history to FILE
+  --import-history FILE  Add the choices in FILE to the history
+  --clear-history        Forget every choice";
+
+/// What to do with the history instead of converting text.
+enum HistoryAction {
+    Export(String),
+    Import(String),
+    Clear,
+}
+
+struct Options {
+    history: Option<HistoryAction>,
+    layout: String,
+    preserve: Option<(char, char)>,
+    reverse: bool,
+    romanize: Option<Scheme>,
+    output: Option<String>,
+    inputs: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        history: None,
+        layout: "phonetic".to_string(),
+        preserve: None,
+        reverse: false,
+        romanize: None,
+        output: None,
+        inputs: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            "-l" | "--layout" => options.layout = value(&arg)?,
+            "-o" | "--output" => options.output = Some(value(&arg)?),
+            "-r" | "--reverse" => options.reverse = true,
+            "--export-history" => options.history = Some(HistoryAction::Export(value(&arg)?)),
+            "--import-history" => options.history = Some(HistoryAction::Import(value(&arg)?)),
+            "--clear-history" => options.history = Some(HistoryAction::Clear),
+            "-R" | "--romanize" => {
+                options.romanize = Some(match value(&arg)?.as_str() {
+                    "iso15919" | "iso" => Scheme::Iso15919,
+                    "ala-lc" | "alalc" => Scheme::AlaLc,
+                    scheme => return Err(format!("unknown romanization {scheme}; use iso15919 or ala-lc")),
+                })
+            }
+            "-p" | "--preserve" => {
+                let delims: Vec<char> = value(&arg)?.chars().collect();
+                match delims[..] {
+                    [open, close] => options.preserve = Some((open, close)),
+                    _ => return Err(format!("{arg} takes exactly two characters, e.g. '{{}}'")),
+                }
+            }
+            "-" => options.inputs.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ => options.inputs.push(arg),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        options.inputs.push("-".to_string());
+    }
+    Ok(options)
+}
+
+/// A built-in layout name, or a path to a layout file. The phonetic layout
+/// is loaded as the other front-ends load it, so the user's own layout and
+/// autocorrect list apply. Avro layouts are imported, with anything that
+/// could not be translated listed on stderr.
+fn load_keyboard(layout: &str) -> Result<BengaliKeyboard, String> {
+    if let Some(fixed) = FixedLayout::builtin(layout) {
+        return Ok(BengaliKeyboard::with_fixed_layout(fixed));
+    }
+
+    let keymap = match layout {
+        "phonetic" => return config::load_keyboard("phonetic").map_err(|e| e.to_string()),
+        path if path.ends_with(".avrolayout") || path.ends_with(".json") => {
+            let (keymap, report) = avro::import_file(path).map_err(|e| e.to_string())?;
+            for line in &report.untranslated {
+                eprintln!("bengali-translit: {path}: {line}");
+            }
+            keymap
+        }
+        path if Path::new(path).is_file() => KeyMap::from_file(path).map_err(|e| e.to_string())?,
+        _ => return Err(format!("unknown layout {layout}")),
+    };
+    Ok(BengaliKeyboard::with_keymap(keymap))
+}
+
+/// Converts lines while tracking escaped segments, which may span lines.
+struct Converter {
+    keyboard: BengaliKeyboard,
+    /// Words the user picked a spelling for are written that way.
+    history: History,
+    in_escape: bool,
+}
+
+impl Converter {
+    /// Converts a line as typing it would: each word is finished, so it is
+    /// autocorrected, and the text between words is converted as it is.
+    fn convert_line(&mut self, line: &str) -> String {
+        let mut input = String::new();
+        if self.in_escape {
+            // Carry the escape over from the previous line
+            input.extend(self.keyboard.escapes().delimiters.map(|(open, _)| open));
+        }
+        input.push_str(line);
+        self.in_escape = self.keyboard.escapes().open_at_end(&input) == Some(OpenEscape::Delimited);
+
+        let mut output = String::new();
+        let mut run = String::new();
+        let mut in_word = false;
+        for ch in input.chars() {
+            let word_char = if in_word {
+                self.keyboard.continues_word(&run, ch)
+            } else {
+                self.keyboard.is_word_char(ch)
+            };
+            if word_char != in_word && !run.is_empty() {
+                output.push_str(&self.convert_run(&run, in_word));
+                run.clear();
+            }
+            in_word = word_char;
+            run.push(ch);
+        }
+        output.push_str(&self.convert_run(&run, in_word));
+        output
+    }
+
+    fn convert_run(&self, run: &str, word: bool) -> String {
+        if word {
+            self.history.finish(&self.keyboard, run)
+        } else {
+            self.keyboard.convert_text(run)
+        }
+    }
+}
+
+fn manage_history(action: HistoryAction) -> Result<(), String> {
+    let path = config::history_path().ok_or("no config directory for the history")?;
+    let mut history = History::load(&path).map_err(|e| e.to_string())?;
+    match action {
+        HistoryAction::Export(file) => return history.save(file).map_err(|e| e.to_string()),
+        HistoryAction::Import(file) => {
+            let count = history.import(&file).map_err(|e| e.to_string())?;
+            eprintln!("bengali-translit: imported {count} choices from {file}");
+        }
+        HistoryAction::Clear => history.clear(),
+    }
+    history.save(&path).map_err(|e| e.to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if let Some(action) = options.history {
+        return manage_history(action);
+    }
+    let mut keyboard = load_keyboard(&options.layout)?;
+    if let Some(delimiters) = options.preserve {
+        let escapes = Escapes { delimiters: Some(delimiters), ..*keyboard.escapes() };
+        keyboard.set_escapes(escapes);
+    }
+    if options.reverse && keyboard.keymap().is_none() {
+        return Err(format!("{}: fixed layouts cannot be reversed", options.layout));
+    }
+    let reverse = options.reverse;
+    let history = config::load_history().unwrap_or_else(|e| {
+        eprintln!("bengali-translit: {e}, ignoring the history");
+        History::new()
+    });
+    let mut converter = Converter { keyboard, history, in_escape: false };
+
+    let output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(File::create(path).map_err(|e| format!("{path}: {e}"))?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut output = BufWriter::new(output);
+
+    for input in &options.inputs {
+        let reader: Box<dyn BufRead> = if input == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(input).map_err(|e| format!("{input}: {e}"))?))
+        };
+
+        for line in reader.lines() {
+            let line = line.map_err(|e| format!("{input}: {e}"))?;
+            let converted = if let Some(scheme) = options.romanize {
+                romanize::romanize(&line, scheme)
+            } else if reverse {
+                converter.keyboard.reverse(&line).unwrap_or_else(|| {
+                    eprintln!("bengali-translit: {input}: cannot reverse {line:?}; copied as is");
+                    line
+                })
+            } else {
+                converter.convert_line(&line)
+            };
+            writeln!(output, "{converted}").map_err(|e| e.to_string())?;
+        }
+    }
+
+    output.flush().map_err(|e| e.to_string())
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(message) = result {
+        eprintln!("bengali-translit: {message}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,122 @@
+//! Per-user files: `%APPDATA%\bengali-keyboard` on Windows,
+//! `$XDG_CONFIG_HOME/bengali-keyboard` (or `~/.config/bengali-keyboard`)
+//! elsewhere.
+
+use crate::autocorrect::Autocorrect;
+use crate::engine::BengaliKeyboard;
+use crate::fixed::FixedLayout;
+use crate::history::{History, HistoryError};
+use crate::hotkey::Hotkeys;
+use crate::keymap::KeyMap;
+use crate::layout::LayoutError;
+use crate::suggest::{Dictionary, DictionaryError};
+use std::env;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "bengali-keyboard";
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|dir| dir.join(APP_DIR))
+}
+
+/// Where a user's own layout lives; it replaces the built-in one when present.
+pub fn user_layout_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("layout.toml"))
+}
+
+/// The user's layout if they have one, otherwise the built-in layout.
+pub fn load_keymap() -> Result<KeyMap, LayoutError> {
+    match user_layout_path() {
+        Some(path) if path.exists() => KeyMap::from_file(path),
+        _ => Ok(KeyMap::new()),
+    }
+}
+
+/// Where a user's own autocorrect list lives; it replaces the bundled one.
+pub fn user_autocorrect_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("autocorrect.toml"))
+}
+
+/// The user's autocorrect list if they have one, otherwise the bundled list.
+pub fn load_autocorrect() -> Result<Autocorrect, LayoutError> {
+    match user_autocorrect_path() {
+        Some(path) if path.exists() => Autocorrect::from_file(path),
+        _ => Ok(Autocorrect::builtin()),
+    }
+}
+
+/// Where a user's own word list lives; it replaces the bundled one.
+pub fn user_dictionary_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("dictionary.txt"))
+}
+
+/// The user's word list if they have one, otherwise the bundled list.
+pub fn load_dictionary() -> Result<Dictionary, DictionaryError> {
+    match user_dictionary_path() {
+        Some(path) if path.exists() => Dictionary::from_file(path),
+        _ => Ok(Dictionary::builtin()),
+    }
+}
+
+/// Where the spellings picked from candidates are remembered.
+pub fn history_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("history.txt"))
+}
+
+/// The user's history, empty if they have none yet.
+pub fn load_history() -> Result<History, HistoryError> {
+    match history_path() {
+        Some(path) => History::load(path),
+        None => Ok(History::new()),
+    }
+}
+
+/// Where a user's hotkeys are set, see [`crate::hotkey`].
+pub fn hotkeys_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("hotkeys.toml"))
+}
+
+/// The user's hotkeys if they have set any, otherwise F10 to toggle.
+pub fn load_hotkeys() -> Result<Hotkeys, LayoutError> {
+    match hotkeys_path() {
+        Some(path) if path.exists() => Hotkeys::from_file(path),
+        _ => Ok(Hotkeys::default()),
+    }
+}
+
+/// Layouts a front-end can switch between, by name: the phonetic layout
+/// (the user's own if they have one) and the built-in fixed layouts.
+pub fn layout_names() -> impl Iterator<Item = &'static str> {
+    std::iter::once("phonetic").chain(FixedLayout::builtin_names())
+}
+
+/// The layout after `name` in [`layout_names`], wrapping around.
+pub fn next_layout(name: &str) -> &'static str {
+    let names: Vec<_> = layout_names().collect();
+    let next = names
+        .iter()
+        .position(|known| *known == name)
+        .map_or(0, |i| (i + 1) % names.len());
+    names[next]
+}
+
+/// The keyboard for one of [`layout_names`]. The phonetic layout comes
+/// with the autocorrect list, whose entries are phonetic spellings.
+pub fn load_keyboard(name: &str) -> Result<BengaliKeyboard, LayoutError> {
+    if name == "phonetic" {
+        let mut keyboard = BengaliKeyboard::with_keymap(load_keymap()?);
+        keyboard.set_autocorrect(load_autocorrect()?);
+        return Ok(keyboard);
+    }
+    FixedLayout::builtin(name)
+        .map(BengaliKeyboard::with_fixed_layout)
+        .ok_or_else(|| LayoutError::Invalid(format!("unknown layout {name}")))
+}
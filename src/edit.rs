@@ -0,0 +1,36 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Keystrokes that turn one rendering of a word into another: erase
+/// `backspaces` characters from the end, then type `insert`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Edit {
+    pub backspaces: usize,
+    pub insert: String,
+}
+
+impl Edit {
+    pub fn is_empty(&self) -> bool {
+        self.backspaces == 0 && self.insert.is_empty()
+    }
+}
+
+/// Computes the smallest [`Edit`] from `old` to `new` that only touches
+/// whole grapheme clusters.
+///
+/// The common prefix is taken at grapheme granularity so that a cluster
+/// which changes shape (ক → কা, ক → ক্ত) is retyped as a unit rather than
+/// having marks appended to a half-shaped letter. Backspaces are counted in
+/// chars, since that is what one backspace removes in most applications.
+pub fn edit_script(old: &str, new: &str) -> Edit {
+    let common: usize = old
+        .graphemes(true)
+        .zip(new.graphemes(true))
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len())
+        .sum();
+
+    Edit {
+        backspaces: old[common..].chars().count(),
+        insert: new[common..].to_string(),
+    }
+}
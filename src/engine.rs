@@ -0,0 +1,302 @@
+use crate::autocorrect::Autocorrect;
+use crate::escape::{Escapes, OpenEscape, Segment};
+use crate::fixed::FixedLayout;
+use crate::keymap::{BengaliChar, Context, ContextRule, KeyMap};
+use crate::state::KeyboardState;
+use crate::trie::Trie;
+
+/// হসন্ত (virama), inserted between consecutive consonants to form conjuncts.
+pub const HASANTA: char = '\u{09CD}';
+
+/// Class of an emitted (or about to be emitted) piece of text.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Boundary,
+    Consonant,
+    Vowel,
+    Other,
+}
+
+impl Class {
+    fn of(bengali_char: &BengaliChar) -> Self {
+        if bengali_char.is_consonant {
+            Class::Consonant
+        } else if bengali_char.is_vowel {
+            Class::Vowel
+        } else {
+            Class::Other
+        }
+    }
+}
+
+impl Context {
+    fn matches(self, class: Class) -> bool {
+        match self {
+            Context::Any => true,
+            Context::Boundary => class == Class::Boundary,
+            Context::Consonant => class == Class::Consonant,
+            Context::Vowel => class == Class::Vowel,
+            Context::NotConsonant => class != Class::Consonant,
+        }
+    }
+}
+
+/// Everything a single trie key can produce: its plain pattern, the kar form
+/// used after a consonant, and any contextual rules for the same key.
+#[derive(Default)]
+struct CompiledPattern {
+    base: Option<BengaliChar>,
+    diacritic: Option<String>,
+    rules: Vec<ContextRule>,
+}
+
+impl CompiledPattern {
+    /// The class this key will most likely emit, used as `next` context.
+    fn class(&self) -> Class {
+        match (&self.base, self.rules.first()) {
+            (Some(base), _) => Class::of(base),
+            (None, Some(rule)) => Class::of(&rule.output),
+            (None, None) => Class::Other,
+        }
+    }
+}
+
+/// The phonetic matcher: greedy longest match over a [`KeyMap`].
+struct Phonetic {
+    keymap: KeyMap,
+    matcher: Trie<CompiledPattern>,
+}
+
+enum Mode {
+    Phonetic(Phonetic),
+    Fixed(FixedLayout),
+}
+
+/// Transliteration engine: phonetic over a [`KeyMap`], or a
+/// [`FixedLayout`] that maps every key to fixed text.
+pub struct BengaliKeyboard {
+    mode: Mode,
+    autocorrect: Autocorrect,
+}
+
+impl BengaliKeyboard {
+    pub fn new() -> Self {
+        Self::with_keymap(KeyMap::new())
+    }
+
+    pub fn with_keymap(keymap: KeyMap) -> Self {
+        let mut matcher: Trie<CompiledPattern> = Trie::new();
+        for (pattern, bengali_char) in &keymap.patterns {
+            let compiled = matcher.entry_or_insert_with(pattern, CompiledPattern::default);
+            compiled.base = Some(bengali_char.clone());
+            compiled.diacritic = keymap.vowel_diacritics.get(pattern).cloned();
+        }
+        for rule in &keymap.rules {
+            matcher
+                .entry_or_insert_with(&rule.pattern, CompiledPattern::default)
+                .rules
+                .push(rule.clone());
+        }
+
+        Self {
+            mode: Mode::Phonetic(Phonetic { keymap, matcher }),
+            autocorrect: Autocorrect::new(),
+        }
+    }
+
+    pub fn with_fixed_layout(layout: FixedLayout) -> Self {
+        Self { mode: Mode::Fixed(layout), autocorrect: Autocorrect::new() }
+    }
+
+    /// Display name of the layout.
+    pub fn name(&self) -> &str {
+        match &self.mode {
+            Mode::Phonetic(phonetic) => &phonetic.keymap.name,
+            Mode::Fixed(layout) => &layout.name,
+        }
+    }
+
+    /// The keymap of a phonetic keyboard.
+    pub fn keymap(&self) -> Option<&KeyMap> {
+        match &self.mode {
+            Mode::Phonetic(phonetic) => Some(&phonetic.keymap),
+            Mode::Fixed(_) => None,
+        }
+    }
+
+    /// The layout of a fixed keyboard.
+    pub fn fixed_layout(&self) -> Option<&FixedLayout> {
+        match &self.mode {
+            Mode::Phonetic(_) => None,
+            Mode::Fixed(layout) => Some(layout),
+        }
+    }
+
+    /// How literal text is typed with this layout.
+    pub fn escapes(&self) -> &Escapes {
+        match &self.mode {
+            Mode::Phonetic(phonetic) => &phonetic.keymap.escapes,
+            Mode::Fixed(layout) => &layout.escapes,
+        }
+    }
+
+    /// Replaces the layout's escape syntax, e.g. with one given on the
+    /// command line.
+    pub fn set_escapes(&mut self, escapes: Escapes) {
+        match &mut self.mode {
+            Mode::Phonetic(phonetic) => phonetic.keymap.escapes = escapes,
+            Mode::Fixed(layout) => layout.escapes = escapes,
+        }
+    }
+
+    /// Corrections for finished words; none unless set.
+    pub fn autocorrect(&self) -> &Autocorrect {
+        &self.autocorrect
+    }
+
+    pub fn set_autocorrect(&mut self, autocorrect: Autocorrect) {
+        self.autocorrect = autocorrect;
+    }
+
+    /// Converts a word that has just been finished: its autocorrection if
+    /// there is one, otherwise [`convert_text`](Self::convert_text).
+    pub fn finish_word(&self, input: &str) -> String {
+        match self.autocorrect.correct(input) {
+            Some(corrected) => corrected.to_string(),
+            None => self.convert_text(input),
+        }
+    }
+
+    /// Whether `ch` can be part of a word: roman letters and the pattern
+    /// punctuation for phonetic layouts, any mapped key for fixed ones, and
+    /// the escape keys.
+    pub fn is_word_char(&self, ch: char) -> bool {
+        let mapped = match &self.mode {
+            Mode::Phonetic(_) => KeyboardState::is_word_char(ch),
+            Mode::Fixed(layout) => layout.key(ch).is_some(),
+        };
+        mapped || self.escapes().is_escape_char(ch)
+    }
+
+    /// Whether typing `ch` after `word` continues the word. Inside an
+    /// escape anything does, except whitespace ending a word escape and a
+    /// newline.
+    pub fn continues_word(&self, word: &str, ch: char) -> bool {
+        match self.escapes().open_at_end(word) {
+            Some(OpenEscape::Delimited) => ch != '\n',
+            Some(OpenEscape::Word) => !ch.is_whitespace(),
+            None => self.is_word_char(ch),
+        }
+    }
+
+    /// What `key` types with AltGr held, for fixed layouts that have an
+    /// AltGr level.
+    pub fn altgr_text(&self, key: char) -> Option<&str> {
+        self.fixed_layout()?.altgr(key)
+    }
+
+    /// Converts a word of typed keys to Bengali.
+    ///
+    /// Phonetic keyboards use greedy longest match. Consecutive consonants
+    /// are joined with [`HASANTA`], so `ndr` gives ন্দ্র without a dedicated
+    /// pattern, unless the keymap turns joining off. Typing `o` (the
+    /// inherent vowel) or the `+` separator between two consonants keeps
+    /// them apart. Keys with [`ContextRule`]s are resolved against the
+    /// previous and next class first; a key that produces nothing in its
+    /// context falls back to the next shorter match.
+    ///
+    /// Fixed keyboards map key by key, see [`FixedLayout::convert_keys`].
+    ///
+    /// Either way, escaped text is copied as it is, see [`Escapes`].
+    pub fn convert_text(&self, input: &str) -> String {
+        let (segments, _) = self.escapes().split(input);
+        let mut result = String::new();
+        for segment in segments {
+            match segment {
+                Segment::Literal(text) => result.push_str(text),
+                Segment::Convert(text) => result.push_str(&match &self.mode {
+                    Mode::Phonetic(phonetic) => phonetic.convert_text(text),
+                    Mode::Fixed(layout) => layout.convert_keys(text),
+                }),
+            }
+        }
+        result
+    }
+}
+
+impl Phonetic {
+    fn convert_text(&self, input: &str) -> String {
+        let mut result = String::new();
+        let mut i = 0;
+        let chars: Vec<char> = input.chars().collect();
+        let mut prev = Class::Boundary;
+        
+        while i < chars.len() {
+            // Longest match first, then shorter ones until one emits
+            let mut end = chars.len();
+            let mut emitted = None;
+            while let Some((compiled, len)) = self.matcher.longest_match(&chars[i..end]) {
+                let next = self.class_at(&chars, i + len);
+                if let Some(class) = self.emit(compiled, prev, next, &mut result) {
+                    emitted = Some((class, len));
+                    break;
+                }
+                end = i + len - 1;
+            }
+
+            match emitted {
+                Some((class, len)) => {
+                    prev = class;
+                    i += len;
+                }
+                None => {
+                    result.push(chars[i]);
+                    prev = Class::Boundary;
+                    i += 1;
+                }
+            }
+        }
+        
+        result
+    }
+
+    /// Appends the output of `compiled` in the given context and returns the
+    /// class of what was emitted, or `None` if the key produces nothing here.
+    fn emit(&self, compiled: &CompiledPattern, prev: Class, next: Class, result: &mut String) -> Option<Class> {
+        if let Some(rule) = compiled
+            .rules
+            .iter()
+            .find(|rule| rule.prev.matches(prev) && rule.next.matches(next))
+        {
+            result.push_str(&rule.output.bengali);
+            return Some(Class::of(&rule.output));
+        }
+
+        let bengali_char = compiled.base.as_ref()?;
+        match &compiled.diacritic {
+            Some(diacritic) if bengali_char.is_vowel && prev == Class::Consonant => {
+                result.push_str(diacritic);
+            }
+            _ => {
+                if self.keymap.join_consonants && bengali_char.is_consonant && prev == Class::Consonant {
+                    result.push(HASANTA);
+                }
+                result.push_str(&bengali_char.bengali);
+            }
+        }
+        Some(Class::of(bengali_char))
+    }
+
+    /// Class of the longest pattern starting at `i`, or a word boundary.
+    fn class_at(&self, chars: &[char], i: usize) -> Class {
+        self.matcher
+            .longest_match(&chars[i..])
+            .map_or(Class::Boundary, |(compiled, _)| compiled.class())
+    }
+}
+
+impl Default for BengaliKeyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+//! Typing literal (English) text inside Bengali.
+//!
+//! A layout's `[escape]` table sets two mechanisms:
+//!
+//! * `delimiters`: text between the two characters is typed as is and the
+//!   delimiters themselves disappear, so `` `.` `` types a literal `.`. The
+//!   text may contain spaces; an unclosed delimiter runs to the end.
+//! * `word`: after this key, the rest of the word up to the next space is
+//!   typed as is.
+
+use crate::layout::LayoutError;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Escapes {
+    /// Opening and closing delimiter of literal text, which may be the same
+    /// character.
+    pub delimiters: Option<(char, char)>,
+    /// Key that makes the rest of the word literal.
+    pub word: Option<char>,
+}
+
+/// An escape still open at the end of some text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenEscape {
+    /// Inside delimiters, until the closing one.
+    Delimited,
+    /// After the word key: it ends at the next whitespace.
+    Word,
+}
+
+/// A piece of text to convert or to keep as it is.
+pub(crate) enum Segment<'a> {
+    Convert(&'a str),
+    Literal(&'a str),
+}
+
+impl Escapes {
+    /// Whether `ch` starts an escape.
+    pub fn is_escape_char(&self, ch: char) -> bool {
+        self.delimiters.is_some_and(|(open, _)| open == ch) || self.word == Some(ch)
+    }
+
+    /// Splits `text` into converted and literal pieces, dropping the escape
+    /// characters, and reports an escape left open at the end.
+    pub(crate) fn split<'a>(&self, text: &'a str) -> (Vec<Segment<'a>>, Option<OpenEscape>) {
+        let mut segments = Vec::new();
+        let mut open = None;
+        let mut start = 0;
+        for (i, ch) in text.char_indices() {
+            let after = i + ch.len_utf8();
+            match open {
+                None if self.is_escape_char(ch) => {
+                    segments.push(Segment::Convert(&text[start..i]));
+                    open = Some(if self.word == Some(ch) { OpenEscape::Word } else { OpenEscape::Delimited });
+                    start = after;
+                }
+                Some(OpenEscape::Delimited) if self.delimiters.is_some_and(|(_, close)| close == ch) => {
+                    segments.push(Segment::Literal(&text[start..i]));
+                    open = None;
+                    start = after;
+                }
+                Some(OpenEscape::Word) if ch.is_whitespace() => {
+                    segments.push(Segment::Literal(&text[start..i]));
+                    open = None;
+                    start = i;
+                }
+                _ => {}
+            }
+        }
+
+        let rest = &text[start..];
+        segments.push(match open {
+            None => Segment::Convert(rest),
+            Some(_) => Segment::Literal(rest),
+        });
+        (segments, open)
+    }
+
+    /// The escape still open at the end of `text`, if any.
+    pub fn open_at_end(&self, text: &str) -> Option<OpenEscape> {
+        self.split(text).1
+    }
+}
+
+/// The `[escape]` table of a layout file.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EscapeEntry {
+    #[serde(default)]
+    delimiters: Option<String>,
+    #[serde(default)]
+    word: Option<String>,
+}
+
+impl EscapeEntry {
+    pub(crate) fn into_escapes(self) -> Result<Escapes, LayoutError> {
+        let delimiters = match self.delimiters.as_deref().map(|d| d.chars().collect::<Vec<_>>()) {
+            None => None,
+            Some(delimiters) => match delimiters[..] {
+                [open, close] if !open.is_whitespace() && !close.is_whitespace() => Some((open, close)),
+                _ => {
+                    return Err(LayoutError::Invalid(
+                        "escape.delimiters must be two characters, e.g. \"``\"".to_string(),
+                    ))
+                }
+            },
+        };
+
+        let word = match self.word.as_deref().map(|w| w.chars().collect::<Vec<_>>()) {
+            None => None,
+            Some(word) => match word[..] {
+                [key] if !key.is_whitespace() => Some(key),
+                _ => return Err(LayoutError::Invalid("escape.word must be a single character".to_string())),
+            },
+        };
+
+        if word.is_some() && delimiters.is_some_and(|(open, _)| Some(open) == word) {
+            return Err(LayoutError::Invalid("escape.word is also escape.delimiters".to_string()));
+        }
+        Ok(Escapes { delimiters, word })
+    }
+}
@@ -0,0 +1,220 @@
+//! Fixed (non-phonetic) layouts such as Bijoy, National and Probhat.
+//!
+//! Every key types the same Bengali text wherever it is pressed. Keys are
+//! named by the character they type on a US keyboard, so `j` and `J` are the
+//! unshifted and shifted J key; the `[altgr]` table holds the AltGr level.
+//!
+//! Bijoy-style layouts are typed in visual order: ি ে ৈ go before the
+//! consonant they follow in Unicode, the reph র্ goes after the syllable it
+//! is written over, and the link key ্ followed by a কার types the
+//! independent vowel. `visual_order = true` turns on all three.
+
+use crate::engine::HASANTA;
+use crate::escape::{EscapeEntry, Escapes};
+use crate::layout::LayoutError;
+use crate::script;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// What the reph key types.
+const REPH: &str = "\u{09B0}\u{09CD}";
+
+/// The built-in fixed layouts by name.
+const BUILTIN: [(&str, &str); 3] = [
+    ("bijoy", include_str!("../layouts/bijoy.toml")),
+    ("national", include_str!("../layouts/national.toml")),
+    ("probhat", include_str!("../layouts/probhat.toml")),
+];
+
+pub struct FixedLayout {
+    /// Display name of the layout.
+    pub name: String,
+    /// Kars and vowels are typed in visual order, see the module docs.
+    pub visual_order: bool,
+    /// How to type literal text, see [`Escapes`].
+    pub escapes: Escapes,
+    keys: HashMap<char, String>,
+    altgr: HashMap<char, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixedLayoutFile {
+    name: String,
+    #[serde(default)]
+    visual_order: bool,
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    altgr: BTreeMap<String, String>,
+    #[serde(default)]
+    escape: EscapeEntry,
+}
+
+fn key_table(table: BTreeMap<String, String>, section: &str) -> Result<HashMap<char, String>, LayoutError> {
+    table
+        .into_iter()
+        .map(|(key, text)| {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) if !ch.is_whitespace() && !text.is_empty() => Ok((ch, text)),
+                (Some(ch), None) if !ch.is_whitespace() => {
+                    Err(LayoutError::Invalid(format!("{section}.{key:?} has no output")))
+                }
+                _ => Err(LayoutError::Invalid(format!(
+                    "{section}.{key:?} is not a single key character"
+                ))),
+            }
+        })
+        .collect()
+}
+
+impl FixedLayout {
+    pub fn from_toml(source: &str) -> Result<Self, LayoutError> {
+        let file: FixedLayoutFile = toml::from_str(source).map_err(LayoutError::Parse)?;
+        Ok(Self {
+            name: file.name,
+            visual_order: file.visual_order,
+            escapes: file.escape.into_escapes()?,
+            keys: key_table(file.keys, "keys")?,
+            altgr: key_table(file.altgr, "altgr")?,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LayoutError::Io(path.to_path_buf(), e))?;
+        Self::from_toml(&source)
+    }
+
+    /// `bijoy`, `national` or `probhat`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, source)| Self::from_toml(source).expect("built-in layout is valid"))
+    }
+
+    /// Names accepted by [`builtin`](Self::builtin).
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN.iter().map(|(name, _)| *name)
+    }
+
+    /// What `key` types, if the layout maps it.
+    pub fn key(&self, key: char) -> Option<&str> {
+        self.keys.get(&key).map(String::as_str)
+    }
+
+    /// What `key` types with AltGr held.
+    pub fn altgr(&self, key: char) -> Option<&str> {
+        self.altgr.get(&key).map(String::as_str)
+    }
+
+    /// Converts typed keys to Bengali in Unicode order. Characters that are
+    /// not keys of the layout, including Bengali text already typed with
+    /// AltGr, are kept as they are.
+    pub fn convert_keys(&self, input: &str) -> String {
+        let mut typed = String::new();
+        for ch in input.chars() {
+            match self.key(ch) {
+                Some(REPH) if self.visual_order => insert_reph(&mut typed),
+                Some(text) => typed.push_str(text),
+                None => typed.push(ch),
+            }
+        }
+        if self.visual_order {
+            logical_order(&typed)
+        } else {
+            typed
+        }
+    }
+}
+
+/// Puts a reph typed after its syllable before the syllable's consonant
+/// cluster. With no consonant to go before, it stays where it was typed.
+fn insert_reph(typed: &mut String) {
+    let chars: Vec<(usize, char)> = typed.char_indices().collect();
+    let mut end = chars.len();
+    while end > 0 && script::is_vowel_sign(chars[end - 1].1) {
+        end -= 1;
+    }
+
+    let mut start = None;
+    loop {
+        let mut i = end;
+        if i > 0 && chars[i - 1].1 == script::NUKTA {
+            i -= 1;
+        }
+        if i == 0 || !script::is_consonant(chars[i - 1].1) {
+            break;
+        }
+        start = Some(chars[i - 1].0);
+        if i > 1 && chars[i - 2].1 == HASANTA {
+            end = i - 2;
+        } else {
+            break;
+        }
+    }
+    match start {
+        Some(at) => typed.insert_str(at, REPH),
+        None => typed.push_str(REPH),
+    }
+}
+
+/// Turns text typed in visual order into Unicode (logical) order.
+fn logical_order(typed: &str) -> String {
+    // The link key followed by a kar is the independent vowel
+    let mut linked = String::new();
+    let mut chars = typed.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match chars.peek().and_then(|&kar| script::independent_vowel(kar)) {
+            Some(vowel) if ch == HASANTA => {
+                linked.push(vowel);
+                chars.next();
+            }
+            _ => linked.push(ch),
+        }
+    }
+
+    // A pre-base kar waits for the consonant cluster it is written before.
+    // If another kar follows the cluster the syllable already has its vowel,
+    // so the pre-base kar stays where it was typed.
+    let mut output = String::new();
+    let mut pending = None;
+    let mut typed_at = 0;
+    let mut in_cluster = false;
+    for ch in linked.chars() {
+        if let Some(kar) = pending {
+            let continues = if in_cluster {
+                ch == HASANTA || ch == script::NUKTA || (script::is_consonant(ch) && output.ends_with(HASANTA))
+            } else {
+                script::is_consonant(ch)
+            };
+            if continues {
+                output.push(ch);
+                in_cluster = true;
+                continue;
+            }
+            let two_part = kar == '\u{09C7}' && matches!(ch, '\u{09BE}' | '\u{09D7}');
+            if in_cluster && script::is_vowel_sign(ch) && !two_part {
+                output.insert(typed_at, kar);
+            } else {
+                output.push(kar);
+            }
+            pending = None;
+            in_cluster = false;
+        }
+
+        if script::is_pre_base_vowel_sign(ch) {
+            pending = Some(ch);
+            typed_at = output.len();
+        } else {
+            output.push(ch);
+        }
+    }
+    output.extend(pending);
+
+    // ে + া and ে + ৗ are the two-part kars ো and ৌ
+    output.replace("\u{09C7}\u{09BE}", "\u{09CB}").replace("\u{09C7}\u{09D7}", "\u{09CC}")
+}
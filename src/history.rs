@@ -0,0 +1,166 @@
+//! The spellings a user has picked, by the roman input they typed.
+//!
+//! Once someone picks শকাল from the candidates for `shokal`, that is what
+//! `shokal` converts to from then on and the first candidate offered for
+//! it. The history is a text file of `input<TAB>word` lines in the config
+//! directory; the same format is used to export and import it.
+//!
+//! Only phonetic layouts have candidates to pick from, so fixed layouts
+//! ignore the history.
+
+use crate::engine::BengaliKeyboard;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Io(PathBuf, std::io::Error),
+    Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            HistoryError::Invalid { line, message } => write!(f, "invalid history, line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HistoryError::Io(_, e) => Some(e),
+            HistoryError::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct History {
+    choices: BTreeMap<String, String>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `input<TAB>word` lines; `#` starts a comment line.
+    pub fn parse(text: &str) -> Result<Self, HistoryError> {
+        let mut history = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('\t') {
+                Some((input, word)) if !input.is_empty() && !word.is_empty() => history.record(input, word),
+                _ => {
+                    return Err(HistoryError::Invalid {
+                        line: index + 1,
+                        message: "expected the typed input, a tab and the word".to_string(),
+                    })
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    /// Reads a history file; a file that does not exist is an empty history.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(HistoryError::Io(path.to_path_buf(), e)),
+        }
+    }
+
+    /// Writes the history to `path`, creating its directory. This is also
+    /// how it is exported.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), HistoryError> {
+        let path = path.as_ref();
+        let io_error = |e| HistoryError::Io(path.to_path_buf(), e);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        // Written whole and then renamed, so a crash never leaves half a file
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, self.to_text()).map_err(io_error)?;
+        fs::rename(&partial, path).map_err(io_error)
+    }
+
+    /// Merges an exported history into this one; its choices win. Returns
+    /// how many it had.
+    pub fn import(&mut self, path: impl AsRef<Path>) -> Result<usize, HistoryError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| HistoryError::Io(path.to_path_buf(), e))?;
+        let imported = Self::parse(&text)?;
+        let count = imported.len();
+        self.choices.extend(imported.choices);
+        Ok(count)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.choices.iter().map(|(input, word)| format!("{input}\t{word}\n")).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.choices.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.choices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.choices.is_empty()
+    }
+
+    /// The word picked last for `input`.
+    pub fn get(&self, input: &str) -> Option<&str> {
+        self.choices.get(input).map(String::as_str)
+    }
+
+    /// Remembers that `word` was picked for `input`. Input that could not be
+    /// saved on one line is ignored.
+    pub fn record(&mut self, input: &str, word: &str) {
+        if !input.contains(['\t', '\n']) && !word.contains('\n') {
+            self.choices.insert(input.to_string(), word.to_string());
+        }
+    }
+
+    /// The word picked for `input` with `keyboard`'s layout, if any.
+    fn choice(&self, keyboard: &BengaliKeyboard, input: &str) -> Option<&str> {
+        keyboard.keymap().and(self.get(input))
+    }
+
+    /// The word picked for `input` if there is one, otherwise
+    /// [`BengaliKeyboard::convert_text`].
+    pub fn convert(&self, keyboard: &BengaliKeyboard, input: &str) -> String {
+        match self.choice(keyboard, input) {
+            Some(word) => word.to_string(),
+            None => keyboard.convert_text(input),
+        }
+    }
+
+    /// The word picked for `input` if there is one, otherwise
+    /// [`BengaliKeyboard::finish_word`]: what to commit once a word is
+    /// finished.
+    pub fn finish(&self, keyboard: &BengaliKeyboard, input: &str) -> String {
+        match self.choice(keyboard, input) {
+            Some(word) => word.to_string(),
+            None => keyboard.finish_word(input),
+        }
+    }
+
+    /// Moves the word picked for `input` to the front of `candidates`.
+    pub fn rank(&self, keyboard: &BengaliKeyboard, input: &str, candidates: &mut Vec<String>) {
+        if let Some(word) = self.choice(keyboard, input) {
+            candidates.retain(|candidate| candidate != word);
+            candidates.insert(0, word.to_string());
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Hotkeys bound to what a front-end can do besides typing.
+//!
+//! A hotkeys file names a hotkey for each action; the ones left out keep
+//! their default, which is F10 for `toggle` and nothing for the others:
+//!
+//! ```toml
+//! toggle = "Ctrl+Space"
+//! convert_selection = "Ctrl+Alt+C"
+//! force_english = "Shift Shift"
+//! switch_layout = "Ctrl+Alt+L"
+//! ```
+//!
+//! A hotkey is one or more chords separated by spaces, each pressed within
+//! [`SEQUENCE_TIMEOUT`] of the one before. A chord is any of `Ctrl`, `Alt`,
+//! `Shift` and `Super` joined with `+` to a key: a letter, digit or
+//! punctuation key, `Space`, `Tab`, `Enter`, `Escape`, `Backspace` or `F1`
+//! to `F24`. A modifier on its own is a tap of that modifier with no other
+//! key, so `Shift Shift` is Shift tapped twice. `""` turns an action off.
+//!
+//! Only the key that completes a hotkey is kept from the application; the
+//! steps before it, and modifier taps, reach it as usual.
+
+use crate::layout::LayoutError;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// Modifier masks for [`Chord::modifiers`]
+pub const CTRL: u8 = 1 << 0;
+pub const ALT: u8 = 1 << 1;
+pub const SHIFT: u8 = 1 << 2;
+pub const SUPER: u8 = 1 << 3;
+
+/// The longest pause between the chords of one hotkey.
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A key as hotkeys name it, whatever the front-end's own key codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A key that types this character without Shift; letters are lower
+    /// case.
+    Char(char),
+    Space,
+    Tab,
+    Enter,
+    Escape,
+    Backspace,
+    /// F1 to F24.
+    F(u8),
+    Ctrl,
+    Alt,
+    Shift,
+    Super,
+}
+
+impl Key {
+    /// The modifier mask a modifier key sets, 0 for other keys.
+    pub fn modifier_mask(self) -> u8 {
+        match self {
+            Key::Ctrl => CTRL,
+            Key::Alt => ALT,
+            Key::Shift => SHIFT,
+            Key::Super => SUPER,
+            _ => 0,
+        }
+    }
+
+    /// The key typing `ch`, with letters folded to lower case.
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            ' ' => Some(Key::Space),
+            '\t' => Some(Key::Tab),
+            '\n' => Some(Key::Enter),
+            crate::BACKSPACE => Some(Key::Backspace),
+            ch if ch.is_ascii_graphic() => Some(Key::Char(ch.to_ascii_lowercase())),
+            _ => None,
+        }
+    }
+
+    /// The key for an X11 key symbol, as IBus, Fcitx5 and X11 report keys.
+    pub fn from_keysym(keysym: u32) -> Option<Self> {
+        match keysym {
+            0xffbe..=0xffd5 => Some(Key::F((keysym - 0xffbe + 1) as u8)),
+            0xff1b => Some(Key::Escape),
+            0xffe1 | 0xffe2 => Some(Key::Shift),
+            0xffe3 | 0xffe4 => Some(Key::Ctrl),
+            0xffe7..=0xffea => Some(Key::Alt),
+            0xffeb | 0xffec => Some(Key::Super),
+            _ => crate::keysym::keysym_to_char(keysym).and_then(Self::from_char),
+        }
+    }
+
+    /// The key for a Windows virtual-key code.
+    pub fn from_vk(vk: u32) -> Option<Self> {
+        use crate::vk::{translate_vk, Modifiers};
+        match vk {
+            0x1b => Some(Key::Escape),
+            0x70..=0x87 => Some(Key::F((vk - 0x70 + 1) as u8)),
+            0x10 | 0xa0 | 0xa1 => Some(Key::Shift),
+            0x11 | 0xa2 | 0xa3 => Some(Key::Ctrl),
+            0x12 | 0xa4 | 0xa5 => Some(Key::Alt),
+            0x5b | 0x5c => Some(Key::Super),
+            _ => translate_vk(vk, Modifiers::default()).and_then(Self::from_char),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        let key = match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Key::Ctrl,
+            "alt" => Key::Alt,
+            "shift" => Key::Shift,
+            "super" | "win" => Key::Super,
+            "space" => Key::Space,
+            "tab" => Key::Tab,
+            "enter" | "return" => Key::Enter,
+            "escape" | "esc" => Key::Escape,
+            "backspace" => Key::Backspace,
+            lower => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=24) => Key::F(n),
+                _ => {
+                    let mut chars = name.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) if ch.is_ascii_graphic() => Key::Char(ch.to_ascii_lowercase()),
+                        _ => return Err(format!("unknown key {name:?}")),
+                    }
+                }
+            },
+        };
+        Ok(key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(ch) => write!(f, "{}", ch.to_ascii_uppercase()),
+            Key::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Modifiers held with a key, or a modifier tapped on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    /// [`CTRL`], [`ALT`], [`SHIFT`] and [`SUPER`].
+    pub modifiers: u8,
+    pub key: Key,
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        // "+" is a key too, as in Ctrl++
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(rest) => (Some(rest), "+"),
+            None if text == "+" => (None, "+"),
+            None => match text.rsplit_once('+') {
+                Some((rest, key)) => (Some(rest), key),
+                None => (None, text),
+            },
+        };
+        let key: Key = key.parse()?;
+        let mut mask = 0;
+        for name in modifiers.into_iter().flat_map(|rest| rest.split('+')) {
+            let modifier = name.parse::<Key>().map(Key::modifier_mask);
+            match modifier {
+                Ok(bit) if bit != 0 => mask |= bit,
+                _ => return Err(format!("{name:?} in {text:?} is not a modifier")),
+            }
+        }
+        if key.modifier_mask() != 0 && mask != 0 {
+            return Err(format!("{text:?} has no key besides modifiers"));
+        }
+        Ok(Self { modifiers: mask, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (bit, name) in [(CTRL, "Ctrl"), (ALT, "Alt"), (SHIFT, "Shift"), (SUPER, "Super")] {
+            if self.modifiers & bit != 0 {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// One or more chords pressed one after the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hotkey(pub Vec<Chord>);
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let chords = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Chord>, _>>()?;
+        if chords.is_empty() {
+            return Err("empty hotkey".to_string());
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Turn conversion on or off.
+    Toggle,
+    /// Replace the selected roman text with its conversion.
+    ConvertSelection,
+    /// Type the next word as it is.
+    ForceEnglish,
+    /// Move on to the next of [`config::layout_names`].
+    ///
+    /// [`config::layout_names`]: crate::config::layout_names
+    SwitchLayout,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HotkeysFile {
+    toggle: Option<String>,
+    convert_selection: Option<String>,
+    force_english: Option<String>,
+    switch_layout: Option<String>,
+}
+
+/// The hotkeys in use, and the chords just pressed that may be the start
+/// of one.
+#[derive(Clone, Debug)]
+pub struct Hotkeys {
+    bindings: Vec<(HotkeyAction, Hotkey)>,
+    /// Chords completed lately, newest last, as long as the longest hotkey.
+    recent: Vec<(Chord, Instant)>,
+    /// A modifier pressed with nothing else, which is a tap if it is let go
+    /// before any other key.
+    tapping: Option<Key>,
+}
+
+impl Default for Hotkeys {
+    /// F10 toggles conversion.
+    fn default() -> Self {
+        let toggle = Hotkey(vec![Chord {
+            modifiers: 0,
+            key: Key::F(10),
+        }]);
+        Self::new(vec![(HotkeyAction::Toggle, toggle)])
+    }
+}
+
+impl Hotkeys {
+    pub fn new(bindings: Vec<(HotkeyAction, Hotkey)>) -> Self {
+        Self {
+            bindings,
+            recent: Vec::new(),
+            tapping: None,
+        }
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, LayoutError> {
+        let file: HotkeysFile = toml::from_str(source).map_err(LayoutError::Parse)?;
+        let mut hotkeys = Self::default();
+        let entries = [
+            (HotkeyAction::Toggle, "toggle", file.toggle),
+            (
+                HotkeyAction::ConvertSelection,
+                "convert_selection",
+                file.convert_selection,
+            ),
+            (HotkeyAction::ForceEnglish, "force_english", file.force_english),
+            (HotkeyAction::SwitchLayout, "switch_layout", file.switch_layout),
+        ];
+        for (action, name, text) in entries {
+            let Some(text) = text else {
+                continue;
+            };
+            hotkeys.bindings.retain(|(bound, _)| *bound != action);
+            if !text.trim().is_empty() {
+                let hotkey = text.parse().map_err(|e| LayoutError::Invalid(format!("{name}: {e}")))?;
+                hotkeys.bindings.push((action, hotkey));
+            }
+        }
+        Ok(hotkeys)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LayoutError::Io(path.to_path_buf(), e))?;
+        Self::from_toml(&source)
+    }
+
+    /// The hotkey for `action`, if it has one.
+    pub fn hotkey(&self, action: HotkeyAction) -> Option<&Hotkey> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, hotkey)| hotkey)
+    }
+
+    /// Follows a key event at `time`, `modifiers` being the modifiers held
+    /// besides the key itself. Returns the action whose hotkey the event
+    /// completes; the front-end then keeps a completing key press (and its
+    /// release) from the application.
+    pub fn key(&mut self, key: Key, modifiers: u8, press: bool, time: Instant) -> Option<HotkeyAction> {
+        let modifiers = modifiers & !key.modifier_mask();
+        let chord = if key.modifier_mask() != 0 {
+            if press {
+                self.tapping = (modifiers == 0).then_some(key);
+                return None;
+            }
+            if self.tapping.take() != Some(key) {
+                return None;
+            }
+            Chord { modifiers: 0, key }
+        } else {
+            if !press {
+                return None;
+            }
+            self.tapping = None;
+            Chord { modifiers, key }
+        };
+        self.chord(chord, time)
+    }
+
+    /// Forgets any hotkey in progress, e.g. when focus moves.
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.tapping = None;
+    }
+
+    fn chord(&mut self, chord: Chord, time: Instant) -> Option<HotkeyAction> {
+        let longest = self
+            .bindings
+            .iter()
+            .map(|(_, hotkey)| hotkey.0.len())
+            .max()
+            .unwrap_or(0);
+        // A pause too long starts over
+        if self
+            .recent
+            .last()
+            .is_some_and(|&(_, last)| time.saturating_duration_since(last) > SEQUENCE_TIMEOUT)
+        {
+            self.recent.clear();
+        }
+        self.recent.push((chord, time));
+        if self.recent.len() > longest {
+            self.recent.remove(0);
+        }
+
+        let (action, _) = self.bindings.iter().find(|(_, hotkey)| {
+            let steps = &hotkey.0;
+            steps.len() <= self.recent.len()
+                && self.recent[self.recent.len() - steps.len()..]
+                    .iter()
+                    .zip(steps)
+                    .all(|((pressed, _), step)| pressed == step)
+        })?;
+        let action = *action;
+        self.recent.clear();
+        Some(action)
+    }
+}
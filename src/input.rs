@@ -0,0 +1,101 @@
+/// Backspace as it appears in key streams handed to [`KeyboardState`].
+///
+/// [`KeyboardState`]: crate::KeyboardState
+pub const BACKSPACE: char = '\x08';
+
+/// Where converted text is written: the focused application's text field.
+pub trait InputSink {
+    /// Erases `count` characters before the cursor.
+    fn send_backspaces(&mut self, count: usize);
+
+    /// Types `text` at the cursor, or commits it with
+    /// [`Strategy::Preedit`].
+    ///
+    /// [`Strategy::Preedit`]: crate::state::Strategy::Preedit
+    fn send_text(&mut self, text: &str);
+
+    /// Shows `text` as the word in progress, not yet part of the document;
+    /// empty hides it. Only called with [`Strategy::Preedit`].
+    ///
+    /// [`Strategy::Preedit`]: crate::state::Strategy::Preedit
+    fn update_preedit(&mut self, _text: &str) {}
+
+    /// Delivers a keystroke the state machine chose not to suppress.
+    ///
+    /// Hook-based platforms let the original event through themselves and
+    /// keep the default no-op; simulated backends apply it here.
+    fn forward_key(&mut self, _ch: char) {}
+}
+
+/// A pull-based stream of typed characters.
+///
+/// Hook-based front-ends (Win32) are push-based and call
+/// [`KeyboardState::process_key`] straight from the hook instead.
+///
+/// [`KeyboardState::process_key`]: crate::KeyboardState::process_key
+pub trait KeySource {
+    fn next_key(&mut self) -> Option<char>;
+}
+
+/// Replays a fixed keystroke script, with [`BACKSPACE`] for the backspace key.
+pub struct ScriptedKeys {
+    keys: std::vec::IntoIter<char>,
+}
+
+impl ScriptedKeys {
+    pub fn new(script: &str) -> Self {
+        Self {
+            keys: script.chars().collect::<Vec<_>>().into_iter(),
+        }
+    }
+}
+
+impl KeySource for ScriptedKeys {
+    fn next_key(&mut self) -> Option<char> {
+        self.keys.next()
+    }
+}
+
+/// In-memory text field with the cursor at the end, recording everything a
+/// front-end does to it.
+#[derive(Debug, Default)]
+pub struct TextField {
+    pub document: String,
+    /// The word in progress shown under the cursor.
+    pub preedit: String,
+    /// Total backspaces and chars sent, to check how minimal the edits are.
+    pub backspaces_sent: usize,
+    pub chars_sent: usize,
+}
+
+impl TextField {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl InputSink for TextField {
+    fn send_backspaces(&mut self, count: usize) {
+        for _ in 0..count {
+            self.document.pop();
+        }
+        self.backspaces_sent += count;
+    }
+
+    fn send_text(&mut self, text: &str) {
+        self.document.push_str(text);
+        self.chars_sent += text.chars().count();
+    }
+
+    fn update_preedit(&mut self, text: &str) {
+        self.preedit = text.to_string();
+    }
+
+    fn forward_key(&mut self, ch: char) {
+        if ch == BACKSPACE {
+            self.document.pop();
+        } else {
+            self.document.push(ch);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! X11 key symbols, as delivered by IBus, Fcitx5 and Wayland/X11 keymaps.
+
+pub const KEY_BACKSPACE: u32 = 0xff08;
+pub const KEY_TAB: u32 = 0xff09;
+pub const KEY_RETURN: u32 = 0xff0d;
+pub const KEY_ESCAPE: u32 = 0xff1b;
+pub const KEY_KP_ENTER: u32 = 0xff8d;
+pub const KEY_F10: u32 = 0xffc7;
+
+/// The character a key symbol types, with [`BACKSPACE`] for the backspace
+/// key and `'\n'` for both Enter keys.
+///
+/// [`BACKSPACE`]: crate::BACKSPACE
+pub fn keysym_to_char(keysym: u32) -> Option<char> {
+    match keysym {
+        // Latin-1 printable keysyms are their own code points
+        0x20..=0x7e => char::from_u32(keysym),
+        KEY_BACKSPACE => Some(crate::BACKSPACE),
+        KEY_TAB => Some('\t'),
+        KEY_RETURN | KEY_KP_ENTER => Some('\n'),
+        _ => None,
+    }
+}
+
+/// Shift, Control, the Lock keys, Alt, Super and the like, which change
+/// other keys rather than type anything themselves.
+pub fn is_modifier_keysym(keysym: u32) -> bool {
+    matches!(keysym, 0xffe1..=0xffee | 0xfe01..=0xfe13 | 0xff7e | 0xff7f)
+}
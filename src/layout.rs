@@ -0,0 +1,185 @@
+//! Loading a [`KeyMap`] from a TOML layout file.
+//!
+//! A layout file has a `[vowels]` table mapping each pattern to its
+//! independent form and kar, `[consonants]` and `[signs]` tables mapping
+//! patterns to text, and an array of `[[rules]]` for contextual forms. The
+//! format is described in full at the top of `layouts/phonetic.toml`, which
+//! is also the built-in default layout.
+
+use crate::escape::EscapeEntry;
+use crate::keymap::{BengaliChar, Context, ContextRule, KeyMap};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The built-in layout, used by [`KeyMap::new`].
+pub const DEFAULT_LAYOUT: &str = include_str!("../layouts/phonetic.toml");
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(PathBuf, std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            LayoutError::Parse(e) => write!(f, "invalid layout file: {e}"),
+            LayoutError::Invalid(message) => write!(f, "invalid layout: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LayoutError::Io(_, e) => Some(e),
+            LayoutError::Parse(e) => Some(e),
+            LayoutError::Invalid(_) => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+    name: String,
+    #[serde(default = "yes")]
+    join_consonants: bool,
+    #[serde(default)]
+    vowels: BTreeMap<String, VowelEntry>,
+    #[serde(default)]
+    consonants: BTreeMap<String, String>,
+    #[serde(default)]
+    signs: BTreeMap<String, String>,
+    #[serde(default)]
+    rules: Vec<RuleEntry>,
+    #[serde(default)]
+    escape: EscapeEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VowelEntry {
+    bengali: String,
+    kar: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    pattern: String,
+    #[serde(default = "any")]
+    prev: Context,
+    #[serde(default = "any")]
+    next: Context,
+    output: String,
+    class: Class,
+}
+
+fn any() -> Context {
+    Context::Any
+}
+
+fn yes() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Class {
+    Consonant,
+    Vowel,
+    Sign,
+}
+
+impl Class {
+    fn bengali_char(&self, bengali: String) -> BengaliChar {
+        BengaliChar {
+            bengali,
+            is_consonant: matches!(self, Class::Consonant),
+            is_vowel: matches!(self, Class::Vowel),
+        }
+    }
+}
+
+fn check_pattern(pattern: &str) -> Result<(), LayoutError> {
+    if pattern.is_empty() {
+        return Err(LayoutError::Invalid("empty pattern".to_string()));
+    }
+    if pattern.chars().any(char::is_whitespace) {
+        return Err(LayoutError::Invalid(format!(
+            "pattern {pattern:?} contains whitespace, which always ends a word"
+        )));
+    }
+    Ok(())
+}
+
+impl KeyMap {
+    /// Parses and validates a layout in the TOML format described in the
+    /// module documentation.
+    pub fn from_toml(source: &str) -> Result<Self, LayoutError> {
+        let file: LayoutFile = toml::from_str(source).map_err(LayoutError::Parse)?;
+
+        let mut patterns = HashMap::new();
+        let mut vowel_diacritics = HashMap::new();
+        let mut add = |pattern: String, bengali_char: BengaliChar, section: &str| {
+            check_pattern(&pattern)?;
+            if bengali_char.bengali.is_empty() && section != "signs" {
+                return Err(LayoutError::Invalid(format!("{section}.{pattern:?} has no output")));
+            }
+            if patterns.contains_key(&pattern) {
+                return Err(LayoutError::Invalid(format!(
+                    "pattern {pattern:?} is defined more than once"
+                )));
+            }
+            patterns.insert(pattern, bengali_char);
+            Ok(())
+        };
+
+        for (pattern, vowel) in file.vowels {
+            vowel_diacritics.insert(pattern.clone(), vowel.kar);
+            add(pattern, Class::Vowel.bengali_char(vowel.bengali), "vowels")?;
+        }
+        for (pattern, bengali) in file.consonants {
+            add(pattern, Class::Consonant.bengali_char(bengali), "consonants")?;
+        }
+        for (pattern, bengali) in file.signs {
+            add(pattern, Class::Sign.bengali_char(bengali), "signs")?;
+        }
+
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|rule| {
+                check_pattern(&rule.pattern)?;
+                Ok(ContextRule {
+                    pattern: rule.pattern,
+                    prev: rule.prev,
+                    next: rule.next,
+                    output: rule.class.bengali_char(rule.output),
+                })
+            })
+            .collect::<Result<_, LayoutError>>()?;
+
+        Ok(Self {
+            name: file.name,
+            patterns,
+            vowel_diacritics,
+            rules,
+            join_consonants: file.join_consonants,
+            escapes: file.escape.into_escapes()?,
+        })
+    }
+
+    /// Reads and validates a layout file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LayoutError::Io(path.to_path_buf(), e))?;
+        Self::from_toml(&source)
+    }
+}
@@ -0,0 +1,38 @@
+//! Platform-independent Bengali phonetic transliteration engine.
+//!
+//! The Win32 tray application in `main.rs` is one front-end; anything that
+//! can hand over a roman input buffer can use the same rules.
+
+pub mod autocorrect;
+pub mod avro;
+pub mod config;
+pub mod edit;
+pub mod engine;
+pub mod escape;
+pub mod fixed;
+pub mod history;
+pub mod hotkey;
+pub mod input;
+pub mod keymap;
+pub mod keysym;
+pub mod layout;
+pub mod romanize;
+mod reverse;
+pub mod script;
+pub mod state;
+pub mod suggest;
+pub mod trie;
+pub mod vk;
+
+pub use autocorrect::Autocorrect;
+pub use edit::{edit_script, Edit};
+pub use engine::{BengaliKeyboard, HASANTA};
+pub use escape::{Escapes, OpenEscape};
+pub use fixed::FixedLayout;
+pub use history::History;
+pub use hotkey::{HotkeyAction, Hotkeys};
+pub use input::{InputSink, KeySource, ScriptedKeys, TextField, BACKSPACE};
+pub use keymap::{BengaliChar, Context, ContextRule, KeyMap};
+pub use layout::LayoutError;
+pub use state::{KeyboardState, Strategy};
+pub use suggest::Dictionary;
@@ -0,0 +1,131 @@
+//! Bengali back to the roman keys that type it.
+//!
+//! Rather than a second table that could drift from the layout, the reverse
+//! converter searches the keymap's own patterns for the shortest input that
+//! [`BengaliKeyboard::convert_text`] turns into the given word. Joining and
+//! separating come out of the layout too: a conjunct is its consonants typed
+//! in a row, and `o` or `+` keep letters apart where they would join.
+
+use crate::engine::{BengaliKeyboard, HASANTA};
+use crate::script;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+/// How far a candidate's output may run past what it shares with the
+/// target: a pattern with a `next` context only settles once the following
+/// pattern is typed. Only one pattern in a row may diverge.
+const MAX_DIVERGENCE: usize = 4;
+
+/// Text `convert_text` should see as one word to reverse.
+fn is_word_char(ch: char) -> bool {
+    script::is_bengali(ch) || matches!(ch, '\u{0964}' | '\u{0965}' | '\u{200C}' | '\u{200D}')
+}
+
+impl BengaliKeyboard {
+    /// The shortest roman input that [`convert_text`](Self::convert_text)
+    /// turns into `text`, or `None` if the layout cannot type it. Text
+    /// outside the Bengali block is kept, between escape delimiters if it
+    /// would otherwise be converted. Fixed layouts are not reversed.
+    pub fn reverse(&self, text: &str) -> Option<String> {
+        let keymap = self.keymap()?;
+        let mut tokens: Vec<&str> = keymap
+            .patterns
+            .keys()
+            .chain(keymap.rules.iter().map(|rule| &rule.pattern))
+            .map(String::as_str)
+            .collect();
+        tokens.sort_unstable();
+        tokens.dedup();
+
+        let mut roman = String::new();
+        let mut rest = text;
+        while let Some(first) = rest.chars().next() {
+            let bengali = is_word_char(first);
+            let end = rest
+                .char_indices()
+                .find(|&(_, ch)| is_word_char(ch) != bengali)
+                .map_or(rest.len(), |(i, _)| i);
+            let (run, tail) = rest.split_at(end);
+
+            if bengali {
+                roman.push_str(&self.reverse_word(run, &tokens)?);
+            } else {
+                // Escape word by word, leaving the spaces between them alone
+                for piece in run.split_inclusive(char::is_whitespace) {
+                    let (word, space) = piece.split_at(piece.trim_end().len());
+                    if self.convert_text(word) == word {
+                        roman.push_str(word);
+                    } else {
+                        let (open, close) = self.escapes().delimiters?;
+                        roman.push(open);
+                        roman.push_str(word);
+                        roman.push(close);
+                    }
+                    roman.push_str(space);
+                }
+            }
+            rest = tail;
+        }
+
+        // Neighbouring runs can still combine, e.g. a comma before ",,"
+        (self.convert_text(&roman) == text).then_some(roman)
+    }
+
+    /// Shortest-first search over sequences of patterns.
+    fn reverse_word(&self, word: &str, tokens: &[&str]) -> Option<String> {
+        let keymap = self.keymap()?;
+        let target: Vec<char> = word.chars().collect();
+
+        // Only patterns that can write something in this word, or nothing
+        let useful: Vec<&str> = tokens
+            .iter()
+            .copied()
+            .filter(|token| {
+                let mut outputs = keymap
+                    .patterns
+                    .get(*token)
+                    .map(|base| base.bengali.as_str())
+                    .into_iter()
+                    .chain(keymap.vowel_diacritics.get(*token).map(String::as_str))
+                    .chain(keymap.rules.iter().filter(|rule| rule.pattern == *token).map(|rule| rule.output.bengali.as_str()));
+                outputs.any(|output| output.chars().all(|ch| target.contains(&ch) || ch == HASANTA))
+            })
+            .collect();
+
+        // Only the last few keys typed can still combine with the next pattern
+        let reach = useful.iter().map(|token| token.chars().count()).max().unwrap_or(1) - 1;
+        let limit = 4 * target.len() + 4;
+        let mut queue = BinaryHeap::new();
+        let mut seen = HashSet::new();
+        // Shortest first; among equals, the one with fewer `+`-style keys
+        queue.push(Reverse((0, 0, String::new(), false)));
+
+        while let Some(Reverse((len, symbols, roman, after_divergence))) = queue.pop() {
+            let output: Vec<char> = self.convert_text(&roman).chars().collect();
+            if output == target {
+                return Some(roman);
+            }
+
+            let common = output.iter().zip(&target).take_while(|(a, b)| a == b).count();
+            let divergence = output.len() - common;
+            if divergence > MAX_DIVERGENCE || (divergence > 0 && after_divergence) {
+                continue;
+            }
+            // Candidates that agree on the output so far and end the same way
+            // continue the same way
+            let tail: String = roman.chars().rev().take(reach).collect();
+            if !seen.insert((output, tail, divergence > 0)) {
+                continue;
+            }
+
+            for token in &useful {
+                let next_len = len + token.chars().count();
+                let next_symbols = symbols + usize::from(!token.chars().any(char::is_alphabetic));
+                if next_len <= limit {
+                    queue.push(Reverse((next_len, next_symbols, roman.clone() + token, divergence > 0)));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,337 @@
+//! Scholarly romanization: ISO 15919 and ALA-LC.
+//!
+//! Unlike the phonetic layouts these are transliterations, letter for letter:
+//! the inherent vowel is always written (কলম → kalama) and a consonant
+//! without it is one followed by hasanta. Where two letters would read as
+//! one, e.g. ক্হ against খ or অই against ঐ, a separator goes between them:
+//! `:` in ISO 15919 and `ʹ` in ALA-LC. Neither has a letter for hasanta
+//! after a vowel, as in অ্যা, so it is left out.
+//!
+//! [`from_roman`] reads romanized text back. Two letters need context: ৎ
+//! and a final ত্ are both `t`, read as ৎ unless ত would form a conjunct
+//! with the next consonant, and ALA-LC writes both ঋ and ড় as `ṛ`, read as
+//! ড় before a vowel and as ঋ otherwise.
+
+use crate::engine::HASANTA;
+use crate::script::{self, NUKTA};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Iso15919,
+    AlaLc,
+}
+
+/// Independent vowels in ISO 15919 and ALA-LC; their কার are written the
+/// same.
+const VOWELS: [(char, &str, &str); 14] = [
+    ('অ', "a", "a"),
+    ('আ', "ā", "ā"),
+    ('ই', "i", "i"),
+    ('ঈ', "ī", "ī"),
+    ('উ', "u", "u"),
+    ('ঊ', "ū", "ū"),
+    ('ঋ', "r̥", "ṛ"),
+    ('ৠ', "r̥̄", "ṝ"),
+    ('ঌ', "l̥", "ḷ"),
+    ('ৡ', "l̥̄", "ḹ"),
+    ('এ', "ē", "e"),
+    ('ঐ', "ai", "ai"),
+    ('ও', "ō", "o"),
+    ('ঔ', "au", "au"),
+];
+
+/// Consonants, which both schemes write alike.
+const CONSONANTS: [(char, &str); 35] = [
+    ('ক', "k"),
+    ('খ', "kh"),
+    ('গ', "g"),
+    ('ঘ', "gh"),
+    ('ঙ', "ṅ"),
+    ('চ', "c"),
+    ('ছ', "ch"),
+    ('জ', "j"),
+    ('ঝ', "jh"),
+    ('ঞ', "ñ"),
+    ('ট', "ṭ"),
+    ('ঠ', "ṭh"),
+    ('ড', "ḍ"),
+    ('ঢ', "ḍh"),
+    ('ণ', "ṇ"),
+    ('ত', "t"),
+    ('থ', "th"),
+    ('দ', "d"),
+    ('ধ', "dh"),
+    ('ন', "n"),
+    ('প', "p"),
+    ('ফ', "ph"),
+    ('ব', "b"),
+    ('ভ', "bh"),
+    ('ম', "m"),
+    ('য', "y"),
+    ('র', "r"),
+    ('ল', "l"),
+    ('শ', "ś"),
+    ('ষ', "ṣ"),
+    ('স', "s"),
+    ('হ', "h"),
+    // ড় ঢ় য়, which text usually has as ড ঢ য and a nukta
+    ('\u{09DC}', "ṛ"),
+    ('\u{09DD}', "ṛh"),
+    ('\u{09DF}', "ẏ"),
+];
+
+/// অনুস্বার, চন্দ্রবিন্দু and বিসর্গ in ISO 15919 and ALA-LC.
+const SIGNS: [(char, &str, &str); 3] = [('ং', "ṁ", "ṃ"), ('ঁ', "m̐", "m̐"), ('ঃ', "ḥ", "ḥ")];
+
+/// Consonants ত forms a conjunct with; before any other, a vowelless `t`
+/// is ৎ.
+const TA_CONJUNCTS: &str = "তথনমযরব";
+
+impl Scheme {
+    fn separator(self) -> char {
+        match self {
+            Scheme::Iso15919 => ':',
+            Scheme::AlaLc => 'ʹ',
+        }
+    }
+
+    fn vowel(self, vowel: char) -> Option<&'static str> {
+        VOWELS
+            .iter()
+            .find(|entry| entry.0 == vowel)
+            .map(|&(_, iso, ala)| self.pick(iso, ala))
+    }
+
+    fn sign(self, sign: char) -> Option<&'static str> {
+        SIGNS.iter().find(|entry| entry.0 == sign).map(|&(_, iso, ala)| self.pick(iso, ala))
+    }
+
+    fn pick(self, iso: &'static str, ala: &'static str) -> &'static str {
+        match self {
+            Scheme::Iso15919 => iso,
+            Scheme::AlaLc => ala,
+        }
+    }
+}
+
+fn consonant(ch: char) -> Option<&'static str> {
+    CONSONANTS.iter().find(|entry| entry.0 == ch).map(|&(_, roman)| roman)
+}
+
+/// ড় ঢ় য় and the letters they are written with before a nukta.
+const NUKTA_FORMS: [(char, char); 3] = [('\u{09DC}', 'ড'), ('\u{09DD}', 'ঢ'), ('\u{09DF}', 'য')];
+
+/// ড ঢ য followed by a nukta, as one letter.
+fn compose_nukta(base: char) -> Option<char> {
+    NUKTA_FORMS.iter().find(|form| form.1 == base).map(|form| form.0)
+}
+
+/// Writes a consonant the way the engine does, ড় ঢ় য় with a nukta.
+fn push_consonant(bengali: &mut String, consonant: char) {
+    match NUKTA_FORMS.iter().find(|form| form.0 == consonant) {
+        Some(&(_, base)) => {
+            bengali.push(base);
+            bengali.push(NUKTA);
+        }
+        None => bengali.push(consonant),
+    }
+}
+
+/// Romanizes Bengali text. Digits become ASCII digits and dandas full
+/// stops; anything outside the Bengali block is kept.
+pub fn romanize(text: &str, scheme: Scheme) -> String {
+    let mut roman = String::new();
+    // A consonant whose inherent vowel is still to be written
+    let mut pending = false;
+    // What the last letters wrote, to spot letters that would run together
+    let mut last_consonant = "";
+    let mut bare_consonant: Option<&str> = None;
+    let mut last_vowel: Option<&str> = None;
+
+    let mut chars = text.chars().peekable();
+    while let Some(mut ch) = chars.next() {
+        if chars.peek() == Some(&NUKTA) {
+            if let Some(composed) = compose_nukta(ch) {
+                ch = composed;
+                chars.next();
+            }
+        }
+
+        if ch == HASANTA {
+            if pending {
+                pending = false;
+                bare_consonant = Some(last_consonant);
+            }
+            continue;
+        }
+        if ch == '\u{200C}' || ch == '\u{200D}' {
+            continue;
+        }
+
+        let kar = script::independent_vowel(ch);
+        let attached = pending && kar.is_some();
+        if pending && !attached {
+            roman.push('a');
+            last_vowel = Some("a");
+        }
+
+        if let Some(letter) = consonant(ch) {
+            if bare_consonant.is_some_and(|bare| is_roman_consonant(&format!("{bare}{letter}"))) {
+                roman.push(scheme.separator());
+            }
+            roman.push_str(letter);
+            last_consonant = letter;
+            pending = true;
+            bare_consonant = None;
+            last_vowel = None;
+            continue;
+        }
+
+        let vowel = kar.or_else(|| script::is_independent_vowel(ch).then_some(ch));
+        match vowel.and_then(|vowel| scheme.vowel(vowel)) {
+            Some(letter) => {
+                if !attached && last_vowel.is_some_and(|last| is_roman_vowel(scheme, &format!("{last}{letter}"))) {
+                    roman.push(scheme.separator());
+                }
+                roman.push_str(letter);
+                last_vowel = Some(letter);
+            }
+            None => {
+                match ch {
+                    'ৎ' => roman.push('t'),
+                    '।' => roman.push('.'),
+                    '॥' => roman.push_str(".."),
+                    _ if script::is_digit(ch) => roman.push(char::from(b'0' + (ch as u32 - 0x09E6) as u8)),
+                    _ => match scheme.sign(ch) {
+                        Some(sign) => roman.push_str(sign),
+                        None => roman.push(ch),
+                    },
+                }
+                last_vowel = None;
+            }
+        }
+        pending = false;
+        bare_consonant = None;
+    }
+    if pending {
+        roman.push('a');
+    }
+    roman
+}
+
+fn is_roman_consonant(roman: &str) -> bool {
+    CONSONANTS.iter().any(|&(_, letter)| letter == roman)
+}
+
+fn is_roman_vowel(scheme: Scheme, roman: &str) -> bool {
+    VOWELS.iter().any(|&(vowel, _, _)| scheme.vowel(vowel) == Some(roman))
+}
+
+#[derive(Clone, Copy)]
+enum Letter {
+    Consonant(char),
+    Vowel(char),
+    Sign(char),
+    Separator,
+}
+
+/// The longest letter of `scheme` at the start of `text`, with its length.
+/// For ALA-LC `ṛ` this is the consonant ড়; the caller decides.
+fn letter_at(text: &str, scheme: Scheme) -> Option<(Letter, usize)> {
+    let consonants = CONSONANTS.iter().map(|&(ch, roman)| (Letter::Consonant(ch), roman));
+    let vowels = VOWELS.iter().map(|&(ch, iso, ala)| (Letter::Vowel(ch), scheme.pick(iso, ala)));
+    let signs = SIGNS.iter().map(|&(ch, iso, ala)| (Letter::Sign(ch), scheme.pick(iso, ala)));
+    let mut separator = [0; 4];
+    let separator: &str = scheme.separator().encode_utf8(&mut separator);
+
+    consonants
+        .chain(vowels)
+        .chain(signs)
+        .chain(std::iter::once((Letter::Separator, separator)))
+        .filter(|(_, roman)| text.starts_with(roman))
+        .max_by_key(|(letter, roman)| (roman.len(), matches!(letter, Letter::Consonant(_))))
+        .map(|(letter, roman)| (letter, roman.len()))
+}
+
+/// Ends a consonant without a vowel: with hasanta, or as ৎ for a ত that
+/// joins nothing.
+fn end_consonant(bengali: &mut String, next: Option<char>) {
+    if bengali.ends_with('ত') && !next.is_some_and(|next| TA_CONJUNCTS.contains(next)) {
+        bengali.pop();
+        bengali.push('ৎ');
+    } else {
+        bengali.push(HASANTA);
+    }
+}
+
+/// Reads text romanized with `scheme` back into Bengali, or `None` if it
+/// has letters the scheme does not use. Case is ignored; the text is
+/// expected in NFC, as [`romanize`] writes it.
+pub fn from_roman(text: &str, scheme: Scheme) -> Option<String> {
+    let text = text.to_lowercase();
+    let mut bengali = String::new();
+    // The last letter was a consonant still waiting for its vowel
+    let mut pending = false;
+
+    let mut rest = text.as_str();
+    while let Some(ch) = rest.chars().next() {
+        let Some((mut letter, len)) = letter_at(rest, scheme) else {
+            if ch.is_alphabetic() {
+                return None;
+            }
+            if pending {
+                end_consonant(&mut bengali, None);
+                pending = false;
+            }
+            match ch {
+                '.' if rest.starts_with("..") => {
+                    bengali.push('॥');
+                    rest = &rest[1..];
+                }
+                '.' => bengali.push('।'),
+                _ => bengali.push(ch),
+            }
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        };
+        rest = &rest[len..];
+
+        // ALA-LC ṛ is ড় before a vowel, ঋ otherwise
+        if scheme == Scheme::AlaLc && matches!(letter, Letter::Consonant('\u{09DC}')) {
+            let before_vowel = matches!(letter_at(rest, scheme), Some((Letter::Vowel(_), _)));
+            if !before_vowel {
+                letter = Letter::Vowel('ঋ');
+            }
+        }
+
+        match letter {
+            Letter::Consonant(consonant) => {
+                if pending {
+                    end_consonant(&mut bengali, Some(consonant));
+                }
+                push_consonant(&mut bengali, consonant);
+                pending = true;
+            }
+            Letter::Vowel(vowel) => {
+                if !pending {
+                    bengali.push(vowel);
+                } else if let Some(kar) = script::vowel_sign(vowel) {
+                    bengali.push(kar);
+                }
+                pending = false;
+            }
+            Letter::Sign(sign) => {
+                if pending {
+                    end_consonant(&mut bengali, None);
+                    pending = false;
+                }
+                bengali.push(sign);
+            }
+            Letter::Separator => {}
+        }
+    }
+    if pending {
+        end_consonant(&mut bengali, None);
+    }
+    Some(bengali)
+}
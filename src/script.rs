@@ -0,0 +1,80 @@
+//! Character classes of the Bengali Unicode block (U+0980–U+09FF).
+
+use crate::keymap::BengaliChar;
+
+/// নুক্তা, written after য ড ঢ in the decomposed forms of য় ড় ঢ়.
+pub const NUKTA: char = '\u{09BC}';
+
+/// ক–হ, ড় ঢ় য় and ৎ.
+pub fn is_consonant(c: char) -> bool {
+    matches!(c, '\u{0995}'..='\u{09B9}' | '\u{09DC}' | '\u{09DD}' | '\u{09DF}' | '\u{09CE}')
+        && !matches!(c, '\u{09A9}' | '\u{09B1}' | '\u{09B3}'..='\u{09B5}')
+}
+
+/// অ–ঔ, ঌ and the vocalic ৠ ৡ.
+pub fn is_independent_vowel(c: char) -> bool {
+    matches!(c, '\u{0985}'..='\u{0994}' | '\u{09E0}' | '\u{09E1}')
+        && !matches!(c, '\u{098D}' | '\u{098E}' | '\u{0991}' | '\u{0992}')
+}
+
+/// কার: া ি ী ু ূ ৃ ৄ ে ৈ ো ৌ ৢ ৣ and the ৌ length mark.
+pub fn is_vowel_sign(c: char) -> bool {
+    matches!(
+        c,
+        '\u{09BE}'..='\u{09C4}' | '\u{09C7}' | '\u{09C8}' | '\u{09CB}' | '\u{09CC}' | '\u{09D7}' | '\u{09E2}' | '\u{09E3}'
+    )
+}
+
+/// Vowel signs written to the left of their consonant: ি ে ৈ.
+pub fn is_pre_base_vowel_sign(c: char) -> bool {
+    matches!(c, '\u{09BF}' | '\u{09C7}' | '\u{09C8}')
+}
+
+/// ০–৯.
+pub fn is_digit(c: char) -> bool {
+    matches!(c, '\u{09E6}'..='\u{09EF}')
+}
+
+pub fn is_bengali(c: char) -> bool {
+    matches!(c, '\u{0980}'..='\u{09FF}')
+}
+
+/// Builds the keymap entry for a piece of Bengali text, classed by its last
+/// character: a consonant, a vowel (independent or কার), or a sign otherwise.
+/// A trailing nukta counts with the letter it modifies.
+pub fn classify(text: &str) -> BengaliChar {
+    let last = text.chars().rev().find(|&c| c != NUKTA);
+    BengaliChar {
+        bengali: text.to_string(),
+        is_consonant: last.is_some_and(is_consonant),
+        is_vowel: last.is_some_and(|c| is_independent_vowel(c) || is_vowel_sign(c)),
+    }
+}
+
+/// কার and the independent vowel each stands for.
+const KARS: [(char, char); 13] = [
+    ('\u{09BE}', 'আ'),
+    ('\u{09BF}', 'ই'),
+    ('\u{09C0}', 'ঈ'),
+    ('\u{09C1}', 'উ'),
+    ('\u{09C2}', 'ঊ'),
+    ('\u{09C3}', 'ঋ'),
+    ('\u{09C4}', 'ৠ'),
+    ('\u{09E2}', 'ঌ'),
+    ('\u{09E3}', 'ৡ'),
+    ('\u{09C7}', 'এ'),
+    ('\u{09C8}', 'ঐ'),
+    ('\u{09CB}', 'ও'),
+    ('\u{09CC}', 'ঔ'),
+];
+
+/// The independent vowel a কার stands for: া → আ, ি → ই and so on.
+pub fn independent_vowel(kar: char) -> Option<char> {
+    KARS.iter().find(|&&(k, _)| k == kar).map(|&(_, vowel)| vowel)
+}
+
+/// The কার of an independent vowel, the inverse of [`independent_vowel`];
+/// অ has none.
+pub fn vowel_sign(vowel: char) -> Option<char> {
+    KARS.iter().find(|&&(_, v)| v == vowel).map(|&(kar, _)| kar)
+}
@@ -0,0 +1,265 @@
+use crate::edit::edit_script;
+use crate::engine::BengaliKeyboard;
+use crate::history::History;
+use crate::input::{InputSink, KeySource, BACKSPACE};
+
+/// How the word in progress reaches the application.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// The converted word is typed into the application as it changes,
+    /// erasing with backspaces: for front-ends that can only send keys.
+    #[default]
+    Direct,
+    /// The converted word is shown with [`InputSink::update_preedit`] and
+    /// only sent with [`InputSink::send_text`] once it is finished, so
+    /// nothing is ever erased: for input method frameworks.
+    Preedit,
+}
+
+/// Per-front-end typing state: whether conversion is on and the word in
+/// progress.
+pub struct KeyboardState {
+    pub enabled: bool,
+    pub strategy: Strategy,
+    /// Keys typed for the current word. AltGr keys of a fixed layout are
+    /// stored as the Bengali text they type.
+    pub input_buffer: String,
+    /// What is on screen (or in the preedit) for the current word, always
+    /// `convert_text(input_buffer)` until the word is finished.
+    pub last_bengali_output: String,
+    /// The next word is typed as it is, see [`force_english_word`].
+    ///
+    /// [`force_english_word`]: Self::force_english_word
+    pub english_word: bool,
+    /// Some of the English word has been typed, so whitespace ends it.
+    english_started: bool,
+}
+
+impl KeyboardState {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            strategy: Strategy::Direct,
+            input_buffer: String::new(),
+            last_bengali_output: String::new(),
+            english_word: false,
+            english_started: false,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.english_word = false;
+        self.reset_word();
+    }
+
+    /// Whether `ch` continues the current word of a phonetic layout rather
+    /// than ending it. See [`BengaliKeyboard::is_word_char`] for any layout.
+    pub fn is_word_char(ch: char) -> bool {
+        ch.is_ascii_alphabetic() || ch.is_ascii_digit() || ".:$_+,".contains(ch)
+    }
+
+    /// Forgets the word in progress without touching the screen.
+    pub fn reset_word(&mut self) {
+        self.input_buffer.clear();
+        self.last_bengali_output.clear();
+    }
+
+    /// Finishes the word in progress and lets the keys of the next one
+    /// through unconverted, up to the whitespace that ends it.
+    pub fn force_english_word(&mut self, keyboard: &BengaliKeyboard, history: &History, sink: &mut impl InputSink) {
+        self.finish_word(keyboard, history, sink);
+        self.english_word = true;
+        self.english_started = false;
+    }
+
+    /// Feeds one typed character into the current word.
+    ///
+    /// With [`Strategy::Direct`] the key itself is always swallowed while a
+    /// word is in progress and the changed tail of the converted word is
+    /// written to `sink` instead, so what is on screen for the current word
+    /// is exactly `last_bengali_output`. With [`Strategy::Preedit`] the word
+    /// is shown as preedit and committed with the space or punctuation that
+    /// ends it. Returns `true` if the original keystroke should be
+    /// suppressed.
+    pub fn process_character(&mut self, keyboard: &BengaliKeyboard, ch: char, sink: &mut impl InputSink) -> bool {
+        self.process_key(keyboard, &History::new(), ch, false, sink)
+    }
+
+    /// Feeds a key pressed with AltGr into the current word. Returns `false`,
+    /// leaving the key to the application, if the layout has no AltGr text
+    /// for it.
+    pub fn process_altgr_character(
+        &mut self,
+        keyboard: &BengaliKeyboard,
+        ch: char,
+        sink: &mut impl InputSink,
+    ) -> bool {
+        self.process_key(keyboard, &History::new(), ch, true, sink)
+    }
+
+    /// [`process_character`], or [`process_altgr_character`] if `altgr`,
+    /// converting with the spellings the user picked in `history`.
+    ///
+    /// [`process_character`]: Self::process_character
+    /// [`process_altgr_character`]: Self::process_altgr_character
+    pub fn process_key(
+        &mut self,
+        keyboard: &BengaliKeyboard,
+        history: &History,
+        ch: char,
+        altgr: bool,
+        sink: &mut impl InputSink,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.english_word {
+            if !ch.is_whitespace() {
+                self.english_started = true;
+            } else if self.english_started {
+                self.english_word = false;
+            }
+            return false;
+        }
+
+        if altgr {
+            let Some(text) = keyboard.altgr_text(ch) else {
+                if self.strategy == Strategy::Preedit {
+                    self.finish_word(keyboard, history, sink);
+                }
+                return false;
+            };
+            self.input_buffer.push_str(text);
+        } else if ch == BACKSPACE {
+            if self.input_buffer.is_empty() {
+                // Nothing of ours left on screen, let the application handle it
+                return false;
+            }
+            self.input_buffer.pop();
+        } else if keyboard.continues_word(&self.input_buffer, ch) {
+            // Includes spaces inside escaped text, which stay part of the word
+            self.input_buffer.push(ch);
+        } else if self.strategy == Strategy::Preedit {
+            if ch == '\n' || ch == '\t' {
+                self.finish_word(keyboard, history, sink);
+                return false;
+            }
+            if self.input_buffer.is_empty() {
+                // No word to commit with it
+                return false;
+            }
+            // Space and punctuation end the word and are committed with it,
+            // so they cannot overtake it
+            let word = history.finish(keyboard, &self.input_buffer) + &ch.to_string();
+            self.clear_preedit(sink);
+            sink.send_text(&word);
+            return true;
+        } else if ch == ' ' || ch == '\n' || ch == '\t' {
+            // Word boundary - the converted word is already on screen unless
+            // it is autocorrected; start a new one and let the
+            // space/newline/tab through
+            self.finish_word(keyboard, history, sink);
+            return false;
+        } else {
+            // Non-matching character, clear buffer
+            self.reset_word();
+            return false;
+        }
+
+        self.retype(keyboard, history, sink);
+        true
+    }
+
+    /// Ends the word in progress, e.g. before a shortcut or when focus
+    /// moves: its final form (autocorrected, or the spelling picked in
+    /// `history`) replaces it on screen or is committed from the preedit.
+    pub fn finish_word(&mut self, keyboard: &BengaliKeyboard, history: &History, sink: &mut impl InputSink) {
+        let word = history.finish(keyboard, &self.input_buffer);
+        match self.strategy {
+            Strategy::Direct => {
+                self.show(word, sink);
+                self.reset_word();
+            }
+            Strategy::Preedit => {
+                let had_word = !self.input_buffer.is_empty();
+                self.clear_preedit(sink);
+                if had_word && !word.is_empty() {
+                    sink.send_text(&word);
+                }
+            }
+        }
+    }
+
+    /// Gives up on converting the word in progress and leaves the keys as
+    /// typed, for Escape. Returns `false` if there is no word.
+    pub fn cancel_word(&mut self, sink: &mut impl InputSink) -> bool {
+        if self.input_buffer.is_empty() {
+            return false;
+        }
+        let roman = std::mem::take(&mut self.input_buffer);
+        match self.strategy {
+            Strategy::Direct => self.show(roman, sink),
+            Strategy::Preedit => {
+                self.clear_preedit(sink);
+                sink.send_text(&roman);
+            }
+        }
+        self.reset_word();
+        true
+    }
+
+    /// Forgets the word and hides its preedit.
+    fn clear_preedit(&mut self, sink: &mut impl InputSink) {
+        let shown = !self.last_bengali_output.is_empty();
+        self.reset_word();
+        if shown {
+            sink.update_preedit("");
+        }
+    }
+
+    /// Reconverts the word and writes what changed to `sink`.
+    fn retype(&mut self, keyboard: &BengaliKeyboard, history: &History, sink: &mut impl InputSink) {
+        let new_bengali = history.convert(keyboard, &self.input_buffer);
+        match self.strategy {
+            Strategy::Direct => self.show(new_bengali, sink),
+            Strategy::Preedit => {
+                sink.update_preedit(&new_bengali);
+                self.last_bengali_output = new_bengali;
+            }
+        }
+    }
+
+    /// Replaces the word on screen with `new_bengali`, writing only what
+    /// changed to `sink`.
+    fn show(&mut self, new_bengali: String, sink: &mut impl InputSink) {
+        let edit = edit_script(&self.last_bengali_output, &new_bengali);
+        self.last_bengali_output = new_bengali;
+
+        // Retype only the part of the word that changed
+        if edit.backspaces > 0 {
+            sink.send_backspaces(edit.backspaces);
+        }
+        if !edit.insert.is_empty() {
+            sink.send_text(&edit.insert);
+        }
+    }
+
+    /// Runs every key from `source` through [`process_character`], handing
+    /// keys that are not suppressed back to `sink`.
+    ///
+    /// [`process_character`]: Self::process_character
+    pub fn replay(&mut self, keyboard: &BengaliKeyboard, source: &mut impl KeySource, sink: &mut impl InputSink) {
+        while let Some(ch) = source.next_key() {
+            if !self.process_character(keyboard, ch, sink) {
+                sink.forward_key(ch);
+            }
+        }
+    }
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,188 @@
+//! Candidate spellings for a word in progress.
+//!
+//! Phonetic input is ambiguous: `shokal` converts to শকাল, but the word is
+//! almost always সকাল. [`BengaliKeyboard::candidates`] respells the input
+//! with the other patterns for each confusable sound, converts every
+//! spelling and keeps those a [`Dictionary`] knows, most frequent first.
+
+use crate::engine::BengaliKeyboard;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The bundled word list, used by [`Dictionary::builtin`].
+pub const DEFAULT_DICTIONARY: &str = include_str!("../dictionary/bengali.txt");
+
+/// Patterns people type for one another. Members a layout lacks are skipped.
+const CONFUSABLE: [&[&str]; 10] = [
+    &["s", "sh", "S", "Sh"],
+    &["n", "N"],
+    &["j", "z"],
+    &["t", "T"],
+    &["th", "Th"],
+    &["d", "D"],
+    &["dh", "Dh"],
+    &["r", "R"],
+    &["i", "I"],
+    &["u", "U"],
+];
+
+/// Caps the respellings of one word; the typed spelling always comes first.
+const MAX_SPELLINGS: usize = 512;
+
+#[derive(Debug)]
+pub enum DictionaryError {
+    Io(PathBuf, std::io::Error),
+    Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictionaryError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            DictionaryError::Invalid { line, message } => write!(f, "invalid dictionary, line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DictionaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DictionaryError::Io(_, e) => Some(e),
+            DictionaryError::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Bengali words and how often they occur.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    counts: HashMap<String, u64>,
+}
+
+impl Dictionary {
+    /// The bundled list of common words.
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_DICTIONARY).expect("built-in dictionary is valid")
+    }
+
+    /// One word per line, optionally followed by whitespace and a count; a
+    /// word without a count counts once. `#` starts a comment line.
+    pub fn parse(text: &str) -> Result<Self, DictionaryError> {
+        let mut counts = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let word = fields.next().unwrap_or_default();
+            let count = match (fields.next(), fields.next()) {
+                (None, _) => 1,
+                (Some(count), None) => count.parse().map_err(|_| DictionaryError::Invalid {
+                    line: index + 1,
+                    message: format!("{count:?} is not a count"),
+                })?,
+                (Some(_), Some(_)) => {
+                    return Err(DictionaryError::Invalid {
+                        line: index + 1,
+                        message: "expected a word and a count".to_string(),
+                    })
+                }
+            };
+            *counts.entry(word.to_string()).or_insert(0) += count;
+        }
+        Ok(Self { counts })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DictionaryError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| DictionaryError::Io(path.to_path_buf(), e))?;
+        Self::parse(&text)
+    }
+
+    /// How often `word` occurs, `None` if it is not in the dictionary.
+    pub fn count(&self, word: &str) -> Option<u64> {
+        self.counts.get(word).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+impl BengaliKeyboard {
+    /// Spellings to offer for the roman `input`, best first: the dictionary
+    /// words it could be meant as by frequency, then what
+    /// [`convert_text`](Self::convert_text) makes of it if that is not one
+    /// of them. Fixed layouts and input with escapes have only the latter.
+    pub fn candidates(&self, input: &str, dictionary: &Dictionary) -> Vec<String> {
+        let typed = self.convert_text(input);
+        if typed.is_empty() {
+            return Vec::new();
+        }
+        if input.chars().any(|ch| self.escapes().is_escape_char(ch)) {
+            return vec![typed];
+        }
+
+        let mut found: Vec<(u64, String)> = Vec::new();
+        for spelling in self.respellings(input) {
+            let word = self.convert_text(&spelling);
+            if let Some(count) = dictionary.count(&word) {
+                if !found.iter().any(|(_, known)| *known == word) {
+                    found.push((count, word));
+                }
+            }
+        }
+        // Stable, so the typed spelling wins ties
+        found.sort_by_key(|&(count, _)| Reverse(count));
+
+        let mut candidates: Vec<String> = found.into_iter().map(|(_, word)| word).collect();
+        if !candidates.contains(&typed) {
+            candidates.push(typed);
+        }
+        candidates
+    }
+
+    /// `input` with each confusable pattern swapped for the others in its
+    /// group, the input itself first.
+    fn respellings(&self, input: &str) -> Vec<String> {
+        let Some(keymap) = self.keymap() else {
+            return vec![input.to_string()];
+        };
+        let known = |pattern: &&str| keymap.patterns.contains_key(*pattern);
+
+        let mut spellings = vec![String::new()];
+        let mut rest = input;
+        while let Some(ch) = rest.chars().next() {
+            let longest = CONFUSABLE
+                .iter()
+                .flat_map(|group| group.iter().map(move |pattern| (*group, *pattern)))
+                .filter(|(_, pattern)| rest.starts_with(pattern) && known(pattern))
+                .max_by_key(|(_, pattern)| pattern.len());
+
+            let (typed, alternatives) = match longest {
+                Some((group, typed)) => (typed, group.iter().copied().filter(|p| *p != typed && known(p)).collect()),
+                None => (&rest[..ch.len_utf8()], Vec::new()),
+            };
+            rest = &rest[typed.len()..];
+
+            spellings = spellings
+                .iter()
+                .flat_map(|spelling| {
+                    std::iter::once(typed)
+                        .chain(alternatives.iter().copied())
+                        .map(move |pattern| format!("{spelling}{pattern}"))
+                })
+                .take(MAX_SPELLINGS)
+                .collect();
+        }
+        spellings
+    }
+}
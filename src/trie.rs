@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+/// Prefix trie over `char` keys used for greedy longest-match lookups.
+///
+/// Built once from a keymap so that matching at a position costs at most the
+/// length of the longest pattern, independent of how many patterns exist.
+pub struct Trie<V> {
+    nodes: Vec<Node<V>>,
+}
+
+struct Node<V> {
+    children: HashMap<char, usize>,
+    value: Option<V>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            children: HashMap::new(),
+            value: None,
+        }
+    }
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node::new()],
+        }
+    }
+
+    /// Returns the value stored under `key`, inserting `default()` first if
+    /// the key is not present yet.
+    pub fn entry_or_insert_with(&mut self, key: &str, default: impl FnOnce() -> V) -> &mut V {
+        let mut node = 0;
+        for ch in key.chars() {
+            node = match self.nodes[node].children.get(&ch) {
+                Some(&next) => next,
+                None => {
+                    let next = self.nodes.len();
+                    self.nodes.push(Node::new());
+                    self.nodes[node].children.insert(ch, next);
+                    next
+                }
+            };
+        }
+        self.nodes[node].value.get_or_insert_with(default)
+    }
+
+    /// Returns the value of the longest key that is a prefix of `input`,
+    /// together with that key's length in chars. Shorter keys are found by
+    /// asking again with `input` cut short of the longest one.
+    pub fn longest_match(&self, input: &[char]) -> Option<(&V, usize)> {
+        let mut node = 0;
+        let mut longest = None;
+
+        for (i, ch) in input.iter().enumerate() {
+            match self.nodes[node].children.get(ch) {
+                Some(&next) => node = next,
+                None => break,
+            }
+            if let Some(value) = &self.nodes[node].value {
+                longest = Some((value, i + 1));
+            }
+        }
+
+        longest
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,65 @@
+//! Windows virtual-key codes, translated without Windows.
+//!
+//! The Win32 front-end asks the active keyboard layout first (`ToUnicodeEx`)
+//! and falls back to [`translate_vk`], which knows the US layout.
+
+pub const VK_BACK: u32 = 0x08;
+pub const VK_TAB: u32 = 0x09;
+pub const VK_RETURN: u32 = 0x0D;
+pub const VK_SPACE: u32 = 0x20;
+pub const VK_OEM_1: u32 = 0xBA;
+pub const VK_OEM_PLUS: u32 = 0xBB;
+pub const VK_OEM_COMMA: u32 = 0xBC;
+pub const VK_OEM_MINUS: u32 = 0xBD;
+pub const VK_OEM_PERIOD: u32 = 0xBE;
+pub const VK_OEM_2: u32 = 0xBF;
+pub const VK_OEM_3: u32 = 0xC0;
+pub const VK_OEM_4: u32 = 0xDB;
+pub const VK_OEM_5: u32 = 0xDC;
+pub const VK_OEM_6: u32 = 0xDD;
+pub const VK_OEM_7: u32 = 0xDE;
+
+/// Modifier state that changes which character a key types.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    /// CapsLock is on. It only affects letters, and Shift reverses it.
+    pub caps_lock: bool,
+}
+
+/// The character a virtual key types on a US keyboard, with
+/// [`BACKSPACE`](crate::BACKSPACE) for the backspace key and `'\n'` for
+/// Enter.
+pub fn translate_vk(vk: u32, modifiers: Modifiers) -> Option<char> {
+    const DIGITS_SHIFTED: &[u8; 10] = b")!@#$%^&*(";
+
+    let shift = modifiers.shift;
+    let pick = |normal: char, shifted: char| Some(if shift { shifted } else { normal });
+    match vk {
+        0x41..=0x5A => {
+            let upper = char::from_u32(vk)?;
+            if shift != modifiers.caps_lock {
+                Some(upper)
+            } else {
+                Some(upper.to_ascii_lowercase())
+            }
+        }
+        0x30..=0x39 => pick(char::from_u32(vk)?, DIGITS_SHIFTED[(vk - 0x30) as usize] as char),
+        VK_BACK => Some(crate::BACKSPACE),
+        VK_TAB => Some('\t'),
+        VK_RETURN => Some('\n'),
+        VK_SPACE => Some(' '),
+        VK_OEM_1 => pick(';', ':'),
+        VK_OEM_PLUS => pick('=', '+'),
+        VK_OEM_COMMA => pick(',', '<'),
+        VK_OEM_MINUS => pick('-', '_'),
+        VK_OEM_PERIOD => pick('.', '>'),
+        VK_OEM_2 => pick('/', '?'),
+        VK_OEM_3 => pick('`', '~'),
+        VK_OEM_4 => pick('[', '{'),
+        VK_OEM_5 => pick('\\', '|'),
+        VK_OEM_6 => pick(']', '}'),
+        VK_OEM_7 => pick('\'', '"'),
+        _ => None,
+    }
+}
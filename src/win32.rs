@@ -0,0 +1,550 @@
+use bengali_keyboard::hotkey::{self, Key};
+use bengali_keyboard::vk::{translate_vk, Modifiers};
+use bengali_keyboard::{config, BengaliKeyboard, History, HotkeyAction, Hotkeys, InputSink, KeyboardState};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use winapi::um::winuser::*;
+use winapi::um::shellapi::*;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winbase::{GlobalLock, GlobalUnlock};
+use winapi::shared::windef::*;
+use winapi::shared::minwindef::*;
+use std::ptr;
+use std::mem;
+
+// Global state
+static KEYBOARD_STATE: Lazy<Arc<Mutex<KeyboardState>>> = Lazy::new(|| {
+    Arc::new(Mutex::new(KeyboardState::new()))
+});
+
+// The user's layout.toml and autocorrect list if they load, the built-in
+// ones otherwise; the tray menu switches to the fixed layouts
+static BENGALI_KEYBOARD: Lazy<RwLock<BengaliKeyboard>> = Lazy::new(|| {
+    RwLock::new(config::load_keyboard("phonetic").unwrap_or_default())
+});
+
+// Name of the layout in BENGALI_KEYBOARD, one of config::layout_names()
+static LAYOUT_NAME: Mutex<&str> = Mutex::new("phonetic");
+
+// The user's hotkeys.toml, or F10 to toggle
+static HOTKEYS: Lazy<Mutex<Hotkeys>> = Lazy::new(|| {
+    Mutex::new(config::load_hotkeys().unwrap_or_else(|e| {
+        eprintln!("bengali_keyboard: {e}");
+        Hotkeys::default()
+    }))
+});
+
+// The spellings picked in the IBus, Fcitx5 and Wayland front-ends
+static HISTORY: Lazy<History> = Lazy::new(|| {
+    config::load_history().unwrap_or_else(|e| {
+        eprintln!("bengali_keyboard: {e}, ignoring the history");
+        History::new()
+    })
+});
+
+// The key that completed a hotkey, whose release is kept from the
+// application too
+static HOTKEY_KEY: Mutex<Option<u32>> = Mutex::new(None);
+
+// The tray window, for the hook to update its icon
+static WINDOW: AtomicUsize = AtomicUsize::new(0);
+
+// Set while a worker thread converts the selection
+static CONVERTING: AtomicBool = AtomicBool::new(false);
+
+const WM_TRAYICON: u32 = WM_USER + 1;
+const ID_TOGGLE: u32 = 1001;
+const ID_EXIT: u32 = 1002;
+// One menu item per config::layout_names(), in order
+const ID_LAYOUT_FIRST: u32 = 1100;
+
+/// Sends converted text to the focused window with `SendInput`.
+struct Win32Sink;
+
+impl InputSink for Win32Sink {
+    fn send_backspaces(&mut self, count: usize) {
+        unsafe {
+            for _ in 0..count {
+                send_backspace();
+            }
+        }
+    }
+
+    fn send_text(&mut self, text: &str) {
+        unsafe { send_unicode_text(text) }
+    }
+}
+
+pub fn run() {
+    unsafe {
+        let h_instance = GetModuleHandleW(ptr::null());
+        
+        let class_name = wide_string("BengaliKeyboardClass");
+        let wc = WNDCLASSW {
+            style: 0,
+            lpfnWndProc: Some(window_proc),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hInstance: h_instance,
+            hIcon: ptr::null_mut(),
+            hCursor: LoadCursorW(ptr::null_mut(), IDC_ARROW),
+            hbrBackground: ptr::null_mut(),
+            lpszMenuName: ptr::null(),
+            lpszClassName: class_name.as_ptr(),
+        };
+
+        RegisterClassW(&wc);
+
+        let hwnd = CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            wide_string("Bengali Keyboard").as_ptr(),
+            0,
+            0, 0, 0, 0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            h_instance,
+            ptr::null_mut(),
+        );
+
+        WINDOW.store(hwnd as usize, Ordering::Relaxed);
+
+        let hook = SetWindowsHookExW(
+            WH_KEYBOARD_LL,
+            Some(keyboard_hook_proc),
+            h_instance,
+            0,
+        );
+
+        if hook.is_null() {
+            return;
+        }
+
+        create_tray_icon(hwnd);
+
+        let mut msg = mem::zeroed();
+        while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+
+        UnhookWindowsHookEx(hook);
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_TRAYICON => {
+            if lparam as UINT == WM_RBUTTONUP {
+                show_context_menu(hwnd);
+            }
+            0
+        }
+        WM_COMMAND => {
+            match LOWORD(wparam as u32) as u32 {
+                ID_TOGGLE => {
+                    toggle_keyboard();
+                    update_tray_icon(hwnd);
+                }
+                ID_EXIT => {
+                    PostQuitMessage(0);
+                }
+                id if id >= ID_LAYOUT_FIRST => {
+                    if let Some(name) = config::layout_names().nth((id - ID_LAYOUT_FIRST) as usize) {
+                        switch_layout(name);
+                    }
+                }
+                _ => {}
+            }
+            0
+        }
+        WM_DESTROY => {
+            remove_tray_icon(hwnd);
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+unsafe extern "system" fn keyboard_hook_proc(
+    code: i32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if code >= 0 {
+        let kbd_struct = *(lparam as *const KBDLLHOOKSTRUCT);
+        let vk_code = kbd_struct.vkCode;
+        
+        // Our own SendInput backspaces come back through this hook; feeding
+        // them to process_key would eat the word we are typing
+        if kbd_struct.flags & LLKHF_INJECTED != 0 {
+            return CallNextHookEx(ptr::null_mut(), code, wparam, lparam);
+        }
+        
+        let key_up = wparam == WM_KEYUP as usize || wparam == WM_SYSKEYUP as usize;
+        if key_up && HOTKEY_KEY.lock().take_if(|key| *key == vk_code).is_some() {
+            return 1;
+        }
+        if let Some(key) = Key::from_vk(vk_code) {
+            let pressed = |vk: i32| (GetAsyncKeyState(vk) & 0x8000u16 as i16) != 0;
+            let modifiers = [
+                (VK_CONTROL, hotkey::CTRL),
+                (VK_MENU, hotkey::ALT),
+                (VK_SHIFT, hotkey::SHIFT),
+                (VK_LWIN, hotkey::SUPER),
+                (VK_RWIN, hotkey::SUPER),
+            ]
+            .into_iter()
+            .filter(|&(vk, _)| pressed(vk))
+            .fold(0, |mask, (_, bit)| mask | bit);
+            let action = HOTKEYS.lock().key(key, modifiers, !key_up, Instant::now());
+            if let Some(action) = action {
+                hotkey_action(action);
+                if !key_up {
+                    *HOTKEY_KEY.lock() = Some(vk_code);
+                    return 1;
+                }
+            }
+        }
+        
+        // AltGr arrives as Ctrl+Right Alt and selects the AltGr level of
+        // fixed layouts
+        let altgr_pressed = (GetAsyncKeyState(VK_RMENU) & 0x8000u16 as i16) != 0;
+        let ctrl_pressed = (GetAsyncKeyState(VK_CONTROL) & 0x8000u16 as i16) != 0;
+        let alt_pressed = (GetAsyncKeyState(VK_MENU) & 0x8000u16 as i16) != 0;
+        let key_down = wparam == WM_KEYDOWN as usize || wparam == WM_SYSKEYDOWN as usize;
+
+        // Ctrl and Alt shortcuts go to the application and end the word
+        if key_down && (ctrl_pressed || alt_pressed) && !altgr_pressed {
+            KEYBOARD_STATE.lock().reset_word();
+            return CallNextHookEx(ptr::null_mut(), code, wparam, lparam);
+        }
+        
+        if key_down {
+            if let Some(ch) = vk_to_char(vk_code, kbd_struct.scanCode, current_modifiers()) {
+                let keyboard = BENGALI_KEYBOARD.read();
+                let mut state = KEYBOARD_STATE.lock();
+                if state.process_key(&keyboard, &HISTORY, ch, altgr_pressed, &mut Win32Sink) {
+                    return 1;
+                }
+            }
+        }
+    }
+    
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+unsafe fn send_backspace() {
+    send_key(VK_BACK as u16, 0);
+    send_key(VK_BACK as u16, KEYEVENTF_KEYUP);
+}
+
+/// Presses, or with `KEYEVENTF_KEYUP` releases, a virtual key.
+unsafe fn send_key(vk: u16, flags: DWORD) {
+    let mut input = INPUT {
+        type_: INPUT_KEYBOARD,
+        u: mem::zeroed(),
+    };
+
+    *input.u.ki_mut() = KEYBDINPUT {
+        wVk: vk,
+        wScan: 0,
+        dwFlags: flags,
+        time: 0,
+        dwExtraInfo: 0,
+    };
+
+    SendInput(1, &mut input, mem::size_of::<INPUT>() as i32);
+}
+
+unsafe fn send_unicode_text(text: &str) {
+    for ch in text.chars() {
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: mem::zeroed(),
+        };
+        
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: 0,
+            wScan: ch as u16,
+            dwFlags: KEYEVENTF_UNICODE,
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        
+        SendInput(1, &mut input, mem::size_of::<INPUT>() as i32);
+        
+        input.u.ki_mut().dwFlags = KEYEVENTF_UNICODE | KEYEVENTF_KEYUP;
+        SendInput(1, &mut input, mem::size_of::<INPUT>() as i32);
+    }
+}
+
+
+
+unsafe fn create_tray_icon(hwnd: HWND) {
+    let mut nid: NOTIFYICONDATAW = mem::zeroed();
+    nid.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    nid.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+    nid.uCallbackMessage = WM_TRAYICON;
+    
+    // Use default icon for now
+    nid.hIcon = LoadIconW(ptr::null_mut(), IDI_APPLICATION);
+    
+    let state = KEYBOARD_STATE.lock();
+    let tooltip = wide_string(&tooltip(state.enabled));
+    
+    for (i, &ch) in tooltip.iter().take(127).enumerate() {
+        nid.szTip[i] = ch;
+    }
+    
+    Shell_NotifyIconW(NIM_ADD, &mut nid);
+}
+
+unsafe fn update_tray_icon(hwnd: HWND) {
+    let mut nid: NOTIFYICONDATAW = mem::zeroed();
+    nid.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    nid.uFlags = NIF_ICON | NIF_TIP;
+    
+    let state = KEYBOARD_STATE.lock();
+    
+    // Use default icon for now
+    nid.hIcon = LoadIconW(ptr::null_mut(), IDI_APPLICATION);
+    
+    let tooltip = wide_string(&tooltip(state.enabled));
+    
+    for (i, &ch) in tooltip.iter().take(127).enumerate() {
+        nid.szTip[i] = ch;
+    }
+    
+    Shell_NotifyIconW(NIM_MODIFY, &mut nid);
+}
+
+unsafe fn remove_tray_icon(hwnd: HWND) {
+    let mut nid: NOTIFYICONDATAW = mem::zeroed();
+    nid.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    
+    Shell_NotifyIconW(NIM_DELETE, &mut nid);
+}
+
+unsafe fn show_context_menu(hwnd: HWND) {
+    let hmenu = CreatePopupMenu();
+    let state = KEYBOARD_STATE.lock();
+    
+    let toggle_text = if state.enabled {
+        wide_string("Disable Bengali Keyboard")
+    } else {
+        wide_string("Enable Bengali Keyboard")
+    };
+    drop(state);
+    
+    AppendMenuW(hmenu, MF_STRING, ID_TOGGLE as usize, toggle_text.as_ptr());
+    AppendMenuW(hmenu, MF_SEPARATOR, 0, ptr::null());
+    let current = *LAYOUT_NAME.lock();
+    for (i, name) in config::layout_names().enumerate() {
+        let flags = if name == current { MF_STRING | MF_CHECKED } else { MF_STRING };
+        let label = wide_string(&layout_label(name));
+        AppendMenuW(hmenu, flags, (ID_LAYOUT_FIRST + i as u32) as usize, label.as_ptr());
+    }
+    AppendMenuW(hmenu, MF_SEPARATOR, 0, ptr::null());
+    AppendMenuW(hmenu, MF_STRING, ID_EXIT as usize, wide_string("Exit").as_ptr());
+    
+    let mut pt = POINT { x: 0, y: 0 };
+    GetCursorPos(&mut pt);
+    
+    SetForegroundWindow(hwnd);
+    TrackPopupMenu(
+        hmenu,
+        TPM_RIGHTBUTTON,
+        pt.x,
+        pt.y,
+        0,
+        hwnd,
+        ptr::null(),
+    );
+    
+    DestroyMenu(hmenu);
+}
+
+fn toggle_keyboard() {
+    KEYBOARD_STATE.lock().toggle();
+}
+
+/// Does what a hotkey is bound to, from the keyboard hook.
+fn hotkey_action(action: HotkeyAction) {
+    match action {
+        HotkeyAction::Toggle => unsafe {
+            toggle_keyboard();
+            update_tray_icon(WINDOW.load(Ordering::Relaxed) as HWND);
+        },
+        HotkeyAction::ForceEnglish => {
+            let keyboard = BENGALI_KEYBOARD.read();
+            KEYBOARD_STATE.lock().force_english_word(&keyboard, &HISTORY, &mut Win32Sink);
+        }
+        HotkeyAction::SwitchLayout => {
+            let next = config::next_layout(*LAYOUT_NAME.lock());
+            switch_layout(next);
+        }
+        HotkeyAction::ConvertSelection => {
+            // The copy waits on other windows, which the hook thread must
+            // never do: while it sleeps, no key reaches anyone
+            if !CONVERTING.swap(true, Ordering::AcqRel) {
+                thread::spawn(|| {
+                    unsafe { convert_selection() };
+                    CONVERTING.store(false, Ordering::Release);
+                });
+            }
+        }
+    }
+}
+
+/// Replaces the selection in the focused window with its conversion: it is
+/// copied with Ctrl+C, read from the clipboard and typed over. Runs on a
+/// worker thread.
+unsafe fn convert_selection() {
+    // The hotkey's own modifiers would turn Ctrl+C into something else
+    let held = || {
+        [VK_CONTROL, VK_MENU, VK_SHIFT, VK_LWIN, VK_RWIN]
+            .into_iter()
+            .any(|vk| (GetAsyncKeyState(vk) & 0x8000u16 as i16) != 0)
+    };
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while held() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let sequence = GetClipboardSequenceNumber();
+    send_key(VK_CONTROL as u16, 0);
+    send_key(b'C' as u16, 0);
+    send_key(b'C' as u16, KEYEVENTF_KEYUP);
+    send_key(VK_CONTROL as u16, KEYEVENTF_KEYUP);
+    let deadline = Instant::now() + Duration::from_millis(500);
+    while GetClipboardSequenceNumber() == sequence {
+        if Instant::now() >= deadline {
+            // Nothing selected
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let Some(text) = clipboard_text() else {
+        return;
+    };
+    let converted = BENGALI_KEYBOARD.read().convert_text(&text);
+    KEYBOARD_STATE.lock().reset_word();
+    // Typing over the selection replaces it
+    send_unicode_text(&converted);
+}
+
+/// The text on the clipboard, if there is any.
+unsafe fn clipboard_text() -> Option<String> {
+    // Only reading, so no window needs to own it
+    if OpenClipboard(ptr::null_mut()) == 0 {
+        return None;
+    }
+    let handle = GetClipboardData(CF_UNICODETEXT);
+    let data = if handle.is_null() { ptr::null() } else { GlobalLock(handle) as *const u16 };
+    let text = (!data.is_null()).then(|| {
+        let len = (0..).take_while(|&i| *data.add(i) != 0).count();
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+        GlobalUnlock(handle);
+        text
+    });
+    CloseClipboard();
+    text
+}
+
+/// Menu text for a layout name: "phonetic" becomes "Phonetic".
+fn layout_label(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn switch_layout(name: &'static str) {
+    match config::load_keyboard(name) {
+        Ok(keyboard) => {
+            *BENGALI_KEYBOARD.write() = keyboard;
+            *LAYOUT_NAME.lock() = name;
+            KEYBOARD_STATE.lock().reset_word();
+        }
+        Err(e) => eprintln!("bengali_keyboard: {e}"),
+    }
+}
+
+/// Shift and CapsLock as they are right now; the hook runs before the
+/// thread's own keyboard state is updated.
+unsafe fn current_modifiers() -> Modifiers {
+    Modifiers {
+        shift: (GetAsyncKeyState(VK_SHIFT) & 0x8000u16 as i16) != 0,
+        caps_lock: (GetKeyState(VK_CAPITAL) & 1) != 0,
+    }
+}
+
+/// The character a key types in the foreground window's keyboard layout,
+/// ignoring Ctrl and Alt so AltGr keys give their base character. Falls back
+/// to the US layout when the active layout has no character for the key;
+/// dead keys give `None` and go to the application untouched.
+unsafe fn vk_to_char(vk_code: u32, scan_code: u32, modifiers: Modifiers) -> Option<char> {
+    let mut key_state = [0u8; 256];
+    if modifiers.shift {
+        key_state[VK_SHIFT as usize] = 0x80;
+    }
+    if modifiers.caps_lock {
+        key_state[VK_CAPITAL as usize] = 0x01;
+    }
+
+    let thread = GetWindowThreadProcessId(GetForegroundWindow(), ptr::null_mut());
+    let layout = GetKeyboardLayout(thread);
+    let mut buffer = [0u16; 8];
+    // Flag 4 keeps ToUnicodeEx from consuming a pending dead key, which
+    // would break accents in the application
+    let len = ToUnicodeEx(
+        vk_code,
+        scan_code,
+        key_state.as_ptr(),
+        buffer.as_mut_ptr(),
+        buffer.len() as i32,
+        4,
+        layout,
+    );
+
+    match len {
+        1 => match char::from_u32(buffer[0] as u32)? {
+            '\r' => Some('\n'),
+            ch => Some(ch),
+        },
+        n if n < 0 => None,
+        _ => translate_vk(vk_code, modifiers),
+    }
+}
+
+/// The tray icon's tooltip, naming the toggle hotkey if there is one.
+fn tooltip(enabled: bool) -> String {
+    let status = if enabled { "Enabled" } else { "Disabled" };
+    match HOTKEYS.lock().hotkey(HotkeyAction::Toggle) {
+        Some(hotkey) => format!("Bengali Keyboard - {status} ({hotkey} to toggle)"),
+        None => format!("Bengali Keyboard - {status}"),
+    }
+}
+
+fn wide_string(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
\ No newline at end of file
@@ -0,0 +1,60 @@
+//! Autocorrection of finished words.
+
+use bengali_keyboard::{Autocorrect, BengaliKeyboard, KeyboardState, ScriptedKeys, TextField};
+
+fn type_script(autocorrect: Autocorrect, script: &str) -> String {
+    let mut keyboard = BengaliKeyboard::new();
+    keyboard.set_autocorrect(autocorrect);
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new(script), &mut field);
+    field.document
+}
+
+#[test]
+fn exact_and_any_case_entries() {
+    let autocorrect = Autocorrect::from_toml(
+        r#"
+        [words]
+        jonno = "জন্য"
+        [any_case]
+        dhaka = "ঢাকা"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(autocorrect.correct("jonno"), Some("জন্য"));
+    // Case is part of a phonetic spelling
+    assert_eq!(autocorrect.correct("Jonno"), None);
+    assert_eq!(autocorrect.correct("Dhaka"), Some("ঢাকা"));
+    assert_eq!(autocorrect.correct("DHAKA"), Some("ঢাকা"));
+    // Whole words only
+    assert_eq!(autocorrect.correct("jonnoi"), None);
+}
+
+#[test]
+fn corrects_on_space_enter_and_tab() {
+    let autocorrect = Autocorrect::builtin();
+    assert_eq!(type_script(autocorrect.clone(), "dhonnobad bhai"), "ধন্যবাদ ভাই");
+    assert_eq!(type_script(autocorrect.clone(), "Dhaka\nami\tbd "), "ঢাকা\nআমি\tবাংলাদেশ ");
+    // Not before the word is finished
+    assert_eq!(type_script(autocorrect, "jonno"), "জন্ন");
+}
+
+#[test]
+fn finished_words_in_preedit_front_ends() {
+    let mut keyboard = BengaliKeyboard::new();
+    assert_eq!(keyboard.finish_word("valo"), "ভাল");
+    keyboard.set_autocorrect(Autocorrect::builtin());
+    assert_eq!(keyboard.finish_word("valo"), "ভালো");
+    assert_eq!(keyboard.finish_word("amar"), "আমার");
+}
+
+#[test]
+fn invalid_files() {
+    let error = |source: &str| Autocorrect::from_toml(source).unwrap_err().to_string();
+    assert_eq!(error("[words]\n\"two words\" = \"x\""), "invalid layout: words.\"two words\" is not a single word");
+    assert_eq!(error("[any_case]\nDhaka = \"ঢাকা\""), "invalid layout: any_case.\"Dhaka\" must be in lower case");
+    assert_eq!(error("[words]\nami = \"\""), "invalid layout: words.\"ami\" has no replacement");
+}
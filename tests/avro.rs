@@ -0,0 +1,93 @@
+//! Importing Avro layouts, and the built-in layout against a sample of
+//! words as Avro Phonetic types them.
+
+use bengali_keyboard::avro::{import_avrolayout, import_file, import_phonetic, ImportError};
+use bengali_keyboard::BengaliKeyboard;
+
+const GRAMMAR: &str = include_str!("data/avro-phonetic.json");
+const FIXED: &str = include_str!("data/fixed.avrolayout");
+const SAMPLE: &str = include_str!("data/avro-sample.txt");
+
+fn sample() -> impl Iterator<Item = (&'static str, &'static str)> {
+    SAMPLE
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split_once('\t').expect("roman<TAB>bengali"))
+}
+
+/// Words of the sample the keyboard types differently from Avro, as
+/// `roman: got (expected)` lines.
+fn mismatches(keyboard: &BengaliKeyboard) -> Vec<String> {
+    sample()
+        .filter_map(|(roman, expected)| {
+            let got = keyboard.convert_text(roman);
+            (got != expected).then(|| format!("{roman}: {got} ({expected})"))
+        })
+        .collect()
+}
+
+#[test]
+fn imported_grammar_matches_sample() {
+    let (keymap, _) = import_phonetic(GRAMMAR).unwrap();
+    assert!(!keymap.join_consonants);
+    let keyboard = BengaliKeyboard::with_keymap(keymap);
+    assert_eq!(mismatches(&keyboard), Vec::<String>::new());
+}
+
+#[test]
+fn grammar_report_lists_untranslated_rules() {
+    let (keymap, report) = import_phonetic(GRAMMAR).unwrap();
+    let report = report.untranslated.join("\n");
+    assert!(report.contains(r#""w" rule 1: prefix exact "k" cannot be expressed"#), "{report}");
+    assert!(report.contains(r#""o" rule 2: suffix !exact "`" ignored"#), "{report}");
+    assert!(report.contains("not case-folded"), "{report}");
+
+    // The skipped rule falls back to the plain pattern.
+    let keyboard = BengaliKeyboard::with_keymap(keymap);
+    assert_eq!(keyboard.convert_text("kw"), "কও");
+}
+
+#[test]
+fn malformed_grammar_is_an_error() {
+    assert!(matches!(import_phonetic("{\"patterns\": 3}"), Err(ImportError::Json(_))));
+    assert!(matches!(
+        import_phonetic(r#"{"patterns": [{"find": "", "replace": "অ"}]}"#),
+        Err(ImportError::Invalid(_))
+    ));
+}
+
+#[test]
+fn fixed_layout_types_each_key() {
+    let (keymap, report) = import_avrolayout(FIXED).unwrap();
+    assert_eq!(keymap.name, "Test Fixed");
+
+    let keyboard = BengaliKeyboard::with_keymap(keymap);
+    assert_eq!(keyboard.convert_text("jfJd1!;."), "কাখি১!ং।");
+    assert_eq!(keyboard.convert_text("q"), "q");
+
+    assert_eq!(report.untranslated.len(), 2, "{:?}", report.untranslated);
+    assert!(report.untranslated[0].starts_with("Key_J_AltGr"));
+    assert!(report.untranslated[1].starts_with("Num0_Normal"));
+}
+
+#[test]
+fn fixed_layout_needs_key_data() {
+    assert!(matches!(import_avrolayout("<Layout/>"), Err(ImportError::Invalid(_))));
+    assert!(matches!(import_avrolayout("<Layout>"), Err(ImportError::Xml(_))));
+}
+
+#[test]
+fn imports_by_extension() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/");
+    assert!(import_file(format!("{dir}avro-phonetic.json")).is_ok());
+    assert!(import_file(format!("{dir}fixed.avrolayout")).is_ok());
+    assert!(matches!(import_file(format!("{dir}avro-sample.txt")), Err(ImportError::Invalid(_))));
+}
+
+/// Where the built-in layout differs from Avro Phonetic on the sample: `oi`
+/// after a consonant is ৈ-কার rather than ই, and `ch` is ছ rather than চ.
+#[test]
+fn default_layout_differs_from_sample_only_where_known() {
+    let keyboard = BengaliKeyboard::new();
+    assert_eq!(mismatches(&keyboard), ["boi: বৈ (বই)", "chondro: ছন্দ্র (চন্দ্র)"]);
+}
@@ -0,0 +1,50 @@
+//! Edit scripts between successive renderings of a word.
+
+use bengali_keyboard::{edit_script, BengaliKeyboard, Edit};
+
+fn edit(backspaces: usize, insert: &str) -> Edit {
+    Edit { backspaces, insert: insert.to_string() }
+}
+
+#[test]
+fn appending_a_new_cluster_only_types_it() {
+    assert_eq!(edit_script("আমা", "আমার"), edit(0, "র"));
+    assert_eq!(edit_script("", "আ"), edit(0, "আ"));
+}
+
+#[test]
+fn changed_cluster_is_retyped_whole() {
+    // ম → মা changes the last cluster, so it is erased and retyped
+    assert_eq!(edit_script("আম", "আমা"), edit(1, "মা"));
+    // ক → ক্ত is one cluster in the result
+    assert_eq!(edit_script("ক", "ক্ত"), edit(1, "ক্ত"));
+}
+
+#[test]
+fn backspaces_count_chars_not_clusters() {
+    assert_eq!(edit_script("কি", "কু"), edit(2, "কু"));
+    assert_eq!(edit_script("ক্ত", ""), edit(3, ""));
+}
+
+#[test]
+fn identical_text_needs_no_edit() {
+    assert!(edit_script("বাংলা", "বাংলা").is_empty());
+}
+
+#[test]
+fn replaying_edits_reproduces_each_conversion() {
+    let keyboard = BengaliKeyboard::new();
+    let mut screen = String::new();
+    let mut previous = String::new();
+
+    for end in 1..="bangladesh".len() {
+        let converted = keyboard.convert_text(&"bangladesh"[..end]);
+        let step = edit_script(&previous, &converted);
+        for _ in 0..step.backspaces {
+            screen.pop();
+        }
+        screen.push_str(&step.insert);
+        assert_eq!(screen, converted);
+        previous = converted;
+    }
+}
@@ -0,0 +1,57 @@
+//! Typing literal English inside Bengali.
+
+use bengali_keyboard::{BengaliKeyboard, Escapes, KeyMap, KeyboardState, OpenEscape, ScriptedKeys, TextField};
+
+fn type_script(script: &str) -> String {
+    let keyboard = BengaliKeyboard::new();
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new(script), &mut field);
+    field.document
+}
+
+#[test]
+fn delimited_text_is_kept() {
+    let keyboard = BengaliKeyboard::new();
+    assert_eq!(keyboard.convert_text("ami `Rust` shikhi"), "আমি Rust শিখি");
+    assert_eq!(keyboard.convert_text("sheSh`.`"), "শেষ.");
+    assert_eq!(keyboard.convert_text("`two words` bhalO"), "two words ভালো");
+    // An unclosed escape runs to the end
+    assert_eq!(keyboard.convert_text("ami `abc def"), "আমি abc def");
+}
+
+#[test]
+fn word_escape_lasts_until_whitespace() {
+    let keyboard = BengaliKeyboard::new();
+    assert_eq!(keyboard.convert_text("\\email@example.com dekhun"), "email@example.com দেখুন");
+    assert_eq!(keyboard.convert_text("ami\\OK ami"), "আমিOK আমি");
+}
+
+#[test]
+fn state_machine_keeps_spaces_inside_an_escape() {
+    assert_eq!(type_script("ami `hello world` boli"), "আমি hello world বলি");
+    assert_eq!(type_script("\\C++ shikhi"), "C++ শিখি");
+    // Backspace over the closing delimiter reopens the escape
+    assert_eq!(type_script("`ab`\x08 c` ka"), "ab c কা");
+}
+
+#[test]
+fn open_escape_is_reported() {
+    let escapes = *BengaliKeyboard::new().escapes();
+    assert_eq!(escapes.open_at_end("ami `abc"), Some(OpenEscape::Delimited));
+    assert_eq!(escapes.open_at_end("ami `abc`"), None);
+    assert_eq!(escapes.open_at_end("\\abc"), Some(OpenEscape::Word));
+    assert_eq!(escapes.open_at_end("\\abc "), None);
+}
+
+#[test]
+fn escapes_are_configured_per_layout() {
+    let source = "name = \"x\"\n[consonants]\nk = \"ক\"\n[escape]\ndelimiters = \"{}\"\n";
+    let keyboard = BengaliKeyboard::with_keymap(KeyMap::from_toml(source).unwrap());
+    assert_eq!(keyboard.escapes(), &Escapes { delimiters: Some(('{', '}')), word: None });
+    assert_eq!(keyboard.convert_text("k{k}k`k`"), "কkক`ক`");
+
+    let invalid = "name = \"x\"\n[escape]\ndelimiters = \"{\"\n";
+    assert!(KeyMap::from_toml(invalid).is_err());
+}
@@ -0,0 +1,113 @@
+//! Fixed layouts: key tables, visual-order typing and the AltGr level.
+
+use bengali_keyboard::{config, BengaliKeyboard, FixedLayout, InputSink, KeyboardState, TextField};
+
+fn keyboard(name: &str) -> BengaliKeyboard {
+    BengaliKeyboard::with_fixed_layout(FixedLayout::builtin(name).unwrap())
+}
+
+#[test]
+fn builtin_layouts_load() {
+    for name in config::layout_names() {
+        assert!(config::load_keyboard(name).is_ok(), "{name}");
+    }
+    assert!(config::load_keyboard("dvorak").is_err());
+    assert_eq!(keyboard("national").name(), "National");
+}
+
+#[test]
+fn bijoy_keys() {
+    let bijoy = keyboard("bijoy");
+    assert_eq!(bijoy.convert_text("jJhH"), "কখবভ");
+    assert_eq!(bijoy.convert_text("123"), "১২৩");
+    // Keys the layout does not map are kept
+    assert_eq!(bijoy.convert_text("j j"), "ক ক");
+}
+
+#[test]
+fn pre_base_kars_move_after_the_consonant() {
+    let bijoy = keyboard("bijoy");
+    assert_eq!(bijoy.convert_text("cj"), "কে");
+    assert_eq!(bijoy.convert_text("Cj"), "কৈ");
+    // The whole cluster, joined with the link key, goes before the kar
+    assert_eq!(bijoy.convert_text("cjgk"), "ক্তে");
+    assert_eq!(bijoy.convert_text("dnz"), "স্রি");
+    // ে + consonant + া is ো
+    assert_eq!(bijoy.convert_text("cjf"), "কো");
+    // A kar with no consonant after it stays where it was typed
+    assert_eq!(bijoy.convert_text("c"), "ে");
+    // So does one whose cluster is followed by another kar
+    assert_eq!(bijoy.convert_text("dkf"), "িতা");
+    assert_eq!(bijoy.convert_text("dkfj"), "িতাক");
+}
+
+#[test]
+fn reph_moves_before_its_syllable() {
+    let bijoy = keyboard("bijoy");
+    assert_eq!(bijoy.convert_text("LmA"), "ধর্ম");
+    assert_eq!(bijoy.convert_text("jmAjkfA"), "কর্মকর্তা");
+    // Kars typed before or after the reph stay with the syllable
+    assert_eq!(bijoy.convert_text("LdmA"), "ধর্মি");
+    assert_eq!(bijoy.convert_text("LmfA"), "ধর্মা");
+    assert_eq!(bijoy.convert_text("LcmAf"), "ধর্মো");
+    // The whole cluster goes after the reph
+    assert_eq!(bijoy.convert_text("jkgkA"), "কর্ত্ত");
+    // With no consonant before it the reph stays where it was typed
+    assert_eq!(bijoy.convert_text("A"), "র্");
+    // র and the link key still type র্ in Unicode order
+    assert_eq!(bijoy.convert_text("vgm"), "র্ম");
+}
+
+#[test]
+fn link_key_and_kar_type_the_vowel() {
+    let bijoy = keyboard("bijoy");
+    assert_eq!(bijoy.convert_text("gf"), "আ");
+    assert_eq!(bijoy.convert_text("gd"), "ই");
+    assert_eq!(bijoy.convert_text("gc"), "এ");
+    assert_eq!(bijoy.convert_text("gfm"), "আম");
+    // Between consonants the link key joins them
+    assert_eq!(bijoy.convert_text("jgj"), "ক্ক");
+}
+
+#[test]
+fn probhat_is_typed_in_logical_order() {
+    let probhat = keyboard("probhat");
+    assert_eq!(probhat.convert_text("k["), "কে");
+    assert_eq!(probhat.convert_text("Am/r"), "অম্র");
+    assert_eq!(probhat.convert_text("bala."), "বালা।");
+}
+
+#[test]
+fn state_machine_types_fixed_layouts() {
+    let national = keyboard("national");
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    let mut field = TextField::new();
+
+    for ch in "cj".chars() {
+        assert!(state.process_character(&national, ch, &mut field));
+    }
+    assert_eq!(field.document, "কে");
+
+    // AltGr+f is আ on the National layout
+    assert!(!state.process_character(&national, ' ', &mut field));
+    field.forward_key(' ');
+    assert!(state.process_altgr_character(&national, 'f', &mut field));
+    assert!(state.process_character(&national, 'm', &mut field));
+    assert_eq!(field.document, "কে আম");
+
+    // Keys without an AltGr level go to the application
+    assert!(!state.process_altgr_character(&national, 'j', &mut field));
+}
+
+#[test]
+fn fixed_layout_files_are_validated() {
+    let error = |source: &str| FixedLayout::from_toml(source).err().unwrap().to_string();
+    assert!(error("name = \"x\"\n[keys]\nab = \"ক\"\n").contains("single key"));
+    assert!(error("name = \"x\"\n[keys]\na = \"\"\n").contains("no output"));
+    assert!(error("name = \"x\"\n[keys]\n\" \" = \"ক\"\n").contains("single key"));
+
+    let layout = FixedLayout::from_toml("name = \"x\"\n[keys]\na = \"ক\"\n[altgr]\na = \"খ\"\n").unwrap();
+    assert!(!layout.visual_order);
+    assert_eq!((layout.key('a'), layout.altgr('a')), (Some("ক"), Some("খ")));
+}
@@ -0,0 +1,64 @@
+//! Remembered candidate choices.
+
+use bengali_keyboard::{BengaliKeyboard, Dictionary, FixedLayout, History};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bengali-history-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn a_pick_comes_first_and_converts() {
+    let keyboard = BengaliKeyboard::new();
+    let mut history = History::new();
+    assert_eq!(history.convert(&keyboard, "shokal"), "শকাল");
+
+    history.record("shokal", "শকাল");
+    let mut candidates = keyboard.candidates("shokal", &Dictionary::builtin());
+    assert_eq!(candidates, ["সকাল", "শকাল"]);
+    history.rank(&keyboard, "shokal", &mut candidates);
+    assert_eq!(candidates, ["শকাল", "সকাল"]);
+
+    history.record("ami", "আমিই");
+    assert_eq!(history.convert(&keyboard, "ami"), "আমিই");
+    // Other input is converted as usual
+    assert_eq!(history.convert(&keyboard, "tumi"), "তুমি");
+}
+
+#[test]
+fn fixed_layouts_ignore_the_history() {
+    let bijoy = BengaliKeyboard::with_fixed_layout(FixedLayout::builtin("bijoy").unwrap());
+    let mut history = History::new();
+    history.record("jk", "x");
+    assert_eq!(history.convert(&bijoy, "jk"), bijoy.convert_text("jk"));
+}
+
+#[test]
+fn saves_exports_and_imports() {
+    let path = temp_path("saved").join("history.txt");
+    let mut history = History::new();
+    history.record("shokal", "শকাল");
+    history.record("ami", "আমিই");
+    history.save(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ami\tআমিই\nshokal\tশকাল\n");
+    assert_eq!(History::load(&path).unwrap(), history);
+
+    let mut other = History::new();
+    other.record("ami", "আমি");
+    other.record("tumi", "তুমি");
+    assert_eq!(other.import(&path).unwrap(), 2);
+    assert_eq!(other.get("ami"), Some("আমিই"));
+    assert_eq!(other.len(), 3);
+
+    other.clear();
+    assert!(other.is_empty());
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn missing_and_invalid_files() {
+    assert!(History::load(temp_path("missing")).unwrap().is_empty());
+    let error = History::parse("ami\tআমি\nbroken\n").unwrap_err();
+    assert_eq!(error.to_string(), "invalid history, line 2: expected the typed input, a tab and the word");
+}
@@ -0,0 +1,149 @@
+//! Hotkey files, and key events matched against them.
+
+use bengali_keyboard::hotkey::{Chord, Hotkey, Key, ALT, CTRL, SHIFT};
+use bengali_keyboard::{config, BengaliKeyboard, History, HotkeyAction, Hotkeys, KeyboardState, TextField};
+use std::time::{Duration, Instant};
+
+fn press(hotkeys: &mut Hotkeys, key: Key, modifiers: u8, time: Instant) -> Option<HotkeyAction> {
+    hotkeys.key(key, modifiers, true, time)
+}
+
+/// Taps `key` with nothing else held.
+fn tap(hotkeys: &mut Hotkeys, key: Key, time: Instant) -> Option<HotkeyAction> {
+    assert_eq!(hotkeys.key(key, 0, true, time), None);
+    hotkeys.key(key, 0, false, time)
+}
+
+#[test]
+fn chords_parse_and_print() {
+    let hotkey: Hotkey = "Ctrl+Alt+b".parse().unwrap();
+    assert_eq!(
+        hotkey.0,
+        [Chord {
+            modifiers: CTRL | ALT,
+            key: Key::Char('b')
+        }]
+    );
+    assert_eq!(hotkey.to_string(), "Ctrl+Alt+B");
+    assert_eq!("ctrl+space".parse::<Hotkey>().unwrap().to_string(), "Ctrl+Space");
+    assert_eq!("Shift  Shift".parse::<Hotkey>().unwrap().to_string(), "Shift Shift");
+    assert_eq!("Ctrl++".parse::<Hotkey>().unwrap().0[0].key, Key::Char('+'));
+    assert_eq!("F24".parse::<Hotkey>().unwrap().0[0].key, Key::F(24));
+
+    for bad in ["", "Ctrl+", "Ctrl+Shift", "Hyper+B", "F25", "Ctrl+Foo"] {
+        assert!(bad.parse::<Hotkey>().is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn file_overrides_the_defaults() {
+    let hotkeys = Hotkeys::default();
+    assert_eq!(hotkeys.hotkey(HotkeyAction::Toggle).unwrap().to_string(), "F10");
+    assert!(hotkeys.hotkey(HotkeyAction::SwitchLayout).is_none());
+
+    let hotkeys = Hotkeys::from_toml(
+        r#"
+        toggle = "Ctrl+Space"
+        switch_layout = "Ctrl+Alt+L"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(hotkeys.hotkey(HotkeyAction::Toggle).unwrap().to_string(), "Ctrl+Space");
+    assert_eq!(
+        hotkeys.hotkey(HotkeyAction::SwitchLayout).unwrap().to_string(),
+        "Ctrl+Alt+L"
+    );
+    assert!(hotkeys.hotkey(HotkeyAction::ForceEnglish).is_none());
+
+    let hotkeys = Hotkeys::from_toml(r#"toggle = """#).unwrap();
+    assert!(hotkeys.hotkey(HotkeyAction::Toggle).is_none());
+
+    assert!(Hotkeys::from_toml(r#"toggle = "Ctrl+""#).is_err());
+    assert!(Hotkeys::from_toml(r#"toggel = "F9""#).is_err());
+}
+
+#[test]
+fn chords_match_with_exactly_their_modifiers() {
+    let mut hotkeys = Hotkeys::from_toml(r#"toggle = "Ctrl+Alt+B""#).unwrap();
+    let now = Instant::now();
+    assert_eq!(press(&mut hotkeys, Key::Char('b'), CTRL, now), None);
+    assert_eq!(press(&mut hotkeys, Key::Char('b'), CTRL | ALT | SHIFT, now), None);
+    assert_eq!(
+        press(&mut hotkeys, Key::Char('b'), CTRL | ALT, now),
+        Some(HotkeyAction::Toggle)
+    );
+    // Releases never complete a chord
+    assert_eq!(hotkeys.key(Key::Char('b'), CTRL | ALT, false, now), None);
+}
+
+#[test]
+fn double_tap_within_the_timeout() {
+    let mut hotkeys = Hotkeys::from_toml(r#"force_english = "Shift Shift""#).unwrap();
+    let now = Instant::now();
+    assert_eq!(tap(&mut hotkeys, Key::Shift, now), None);
+    let soon = now + Duration::from_millis(200);
+    assert_eq!(tap(&mut hotkeys, Key::Shift, soon), Some(HotkeyAction::ForceEnglish));
+
+    // Too slow
+    let later = soon + Duration::from_secs(1);
+    assert_eq!(tap(&mut hotkeys, Key::Shift, later), None);
+    assert_eq!(tap(&mut hotkeys, Key::Shift, later + Duration::from_secs(1)), None);
+
+    // Shift used for a capital is not a tap
+    let then = later + Duration::from_secs(5);
+    assert_eq!(tap(&mut hotkeys, Key::Shift, then), None);
+    assert_eq!(hotkeys.key(Key::Shift, 0, true, then), None);
+    assert_eq!(press(&mut hotkeys, Key::Char('a'), SHIFT, then), None);
+    assert_eq!(hotkeys.key(Key::Shift, SHIFT, false, then), None);
+    assert_eq!(tap(&mut hotkeys, Key::Shift, then), None);
+}
+
+#[test]
+fn sequences_of_chords() {
+    let mut hotkeys = Hotkeys::from_toml(r#"convert_selection = "Ctrl+K Ctrl+B""#).unwrap();
+    let now = Instant::now();
+    assert_eq!(press(&mut hotkeys, Key::Char('b'), CTRL, now), None);
+    assert_eq!(press(&mut hotkeys, Key::Char('k'), CTRL, now), None);
+    assert_eq!(
+        press(&mut hotkeys, Key::Char('b'), CTRL, now),
+        Some(HotkeyAction::ConvertSelection)
+    );
+    assert_eq!(press(&mut hotkeys, Key::Char('b'), CTRL, now), None);
+}
+
+#[test]
+fn native_key_codes() {
+    assert_eq!(Key::from_keysym(0xffc7), Some(Key::F(10)));
+    assert_eq!(Key::from_keysym(u32::from('B')), Some(Key::Char('b')));
+    assert_eq!(Key::from_keysym(0x20), Some(Key::Space));
+    assert_eq!(Key::from_keysym(0xffe2), Some(Key::Shift));
+    assert_eq!(Key::from_vk(0x79), Some(Key::F(10)));
+    assert_eq!(Key::from_vk(0x42), Some(Key::Char('b')));
+    assert_eq!(Key::from_vk(0xa2), Some(Key::Ctrl));
+}
+
+#[test]
+fn forced_english_word_is_left_alone() {
+    let keyboard = BengaliKeyboard::new();
+    let history = History::new();
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    let mut field = TextField::new();
+    for ch in "ami".chars() {
+        assert!(state.process_character(&keyboard, ch, &mut field));
+    }
+    state.force_english_word(&keyboard, &history, &mut field);
+    for ch in " email ".chars() {
+        assert!(!state.process_character(&keyboard, ch, &mut field));
+        field.document.push(ch);
+    }
+    assert!(state.process_character(&keyboard, 'i', &mut field));
+    assert_eq!(field.document, "আমি email ই");
+}
+
+#[test]
+fn layouts_cycle() {
+    assert_eq!(config::next_layout("phonetic"), "bijoy");
+    let last = config::layout_names().last().unwrap();
+    assert_eq!(config::next_layout(last), "phonetic");
+}
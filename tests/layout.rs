@@ -0,0 +1,82 @@
+//! Layout files: parsing, validation and using a custom layout.
+
+use bengali_keyboard::{BengaliKeyboard, KeyMap, LayoutError};
+
+const SMALL_LAYOUT: &str = r#"
+name = "House style"
+
+[vowels]
+a = { bengali = "আ", kar = "া" }
+o = { bengali = "অ", kar = "" }
+
+[consonants]
+k = "ক"
+sh = "শ"
+s = "শ"
+r = "র"
+
+[signs]
+"." = "।"
+
+[[rules]]
+pattern = "y"
+prev = "consonant"
+output = "্য"
+class = "consonant"
+"#;
+
+fn invalid(source: &str) -> String {
+    match KeyMap::from_toml(source) {
+        Err(e) => e.to_string(),
+        Ok(_) => panic!("layout should be rejected:\n{source}"),
+    }
+}
+
+#[test]
+fn custom_layout_drives_the_engine() {
+    let keymap = KeyMap::from_toml(SMALL_LAYOUT).unwrap();
+    assert_eq!(keymap.name, "House style");
+
+    let keyboard = BengaliKeyboard::with_keymap(keymap);
+    assert_eq!(keyboard.convert_text("sakar."), "শাকার।");
+    assert_eq!(keyboard.convert_text("kya"), "ক্যা");
+    // Patterns the layout does not define pass through
+    assert_eq!(keyboard.convert_text("bak"), "bআক");
+}
+
+#[test]
+fn built_in_layout_loads() {
+    let keymap = KeyMap::new();
+    assert_eq!(keymap.name, "Phonetic");
+    assert_eq!(keymap.patterns["kh"].bengali, "খ");
+    assert_eq!(keymap.vowel_diacritics["i"], "ি");
+    assert!(!keymap.rules.is_empty());
+}
+
+#[test]
+fn rejects_duplicate_patterns() {
+    let message = invalid("name = \"x\"\n[consonants]\nk = \"ক\"\n[signs]\nk = \"ং\"\n");
+    assert!(message.contains("more than once"), "{message}");
+}
+
+#[test]
+fn rejects_bad_patterns_and_outputs() {
+    invalid("name = \"x\"\n[consonants]\n\"\" = \"ক\"\n");
+    invalid("name = \"x\"\n[consonants]\n\"k k\" = \"ক\"\n");
+    invalid("name = \"x\"\n[consonants]\nk = \"\"\n");
+}
+
+#[test]
+fn rejects_unknown_fields_and_contexts() {
+    invalid("name = \"x\"\n[vowels]\na = { bengali = \"আ\", kar = \"া\", extra = 1 }\n");
+    invalid("name = \"x\"\n[[rules]]\npattern = \"y\"\nprev = \"sometimes\"\noutput = \"য\"\nclass = \"consonant\"\n");
+    invalid("name = \"x\"\n[[rules]]\npattern = \"y\"\noutput = \"য\"\nclass = \"letter\"\n");
+}
+
+#[test]
+fn missing_file_reports_the_path() {
+    match KeyMap::from_file("/nonexistent/layout.toml") {
+        Err(LayoutError::Io(path, _)) => assert!(path.ends_with("layout.toml")),
+        _ => panic!("expected an I/O error"),
+    }
+}
@@ -0,0 +1,114 @@
+//! The preedit strategy: the word in progress stays out of the document
+//! until it is finished.
+
+use bengali_keyboard::{BengaliKeyboard, History, KeyboardState, ScriptedKeys, Strategy, TextField};
+
+fn preedit_state() -> KeyboardState {
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    state.strategy = Strategy::Preedit;
+    state
+}
+
+fn type_script(script: &str) -> TextField {
+    let keyboard = BengaliKeyboard::new();
+    let mut field = TextField::new();
+    preedit_state().replay(&keyboard, &mut ScriptedKeys::new(script), &mut field);
+    field
+}
+
+#[test]
+fn words_are_committed_at_a_boundary() {
+    let field = type_script("amar sOnar bangla");
+    assert_eq!(field.document, "আমার সোনার ");
+    assert_eq!(field.preedit, "বাংলা");
+    // Nothing is ever typed only to be erased
+    assert_eq!(field.backspaces_sent, 0);
+
+    let field = type_script("2024 sal.!");
+    assert_eq!(field.document, "২০২৪ সাল।!");
+    assert_eq!(field.preedit, "");
+}
+
+#[test]
+fn boundaries_without_a_word_reach_the_application() {
+    let keyboard = BengaliKeyboard::new();
+    let mut state = preedit_state();
+    let mut field = TextField::new();
+    for ch in [' ', '!'] {
+        assert!(!state.process_character(&keyboard, ch, &mut field));
+    }
+    // Nothing was committed in their place
+    assert_eq!(field.document, "");
+
+    // Nor after a word that punctuation has already committed
+    assert!(state.process_character(&keyboard, 'a', &mut field));
+    assert!(state.process_character(&keyboard, '!', &mut field));
+    assert!(!state.process_character(&keyboard, '?', &mut field));
+    assert_eq!(field.document, "আ!");
+}
+
+#[test]
+fn backspace_edits_the_preedit_then_reaches_the_application() {
+    let field = type_script("amarr\x08 bhai");
+    assert_eq!(field.document, "আমার ");
+    assert_eq!(field.preedit, "ভাই");
+
+    let field = type_script("ka\x08\x08 \x08\x08");
+    assert_eq!(field.document, "");
+    assert_eq!(field.preedit, "");
+    assert_eq!(field.backspaces_sent, 0);
+}
+
+#[test]
+fn newline_finishes_the_word_and_passes_through() {
+    let field = type_script("ami\nbhat");
+    assert_eq!(field.document, "আমি\n");
+    assert_eq!(field.preedit, "ভাত");
+}
+
+#[test]
+fn finish_and_cancel() {
+    let keyboard = BengaliKeyboard::new();
+    let history = History::new();
+    let mut state = preedit_state();
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new("ami"), &mut field);
+    state.finish_word(&keyboard, &history, &mut field);
+    assert_eq!((field.document.as_str(), field.preedit.as_str()), ("আমি", ""));
+    assert!(state.input_buffer.is_empty());
+
+    state.replay(&keyboard, &mut ScriptedKeys::new(" kor"), &mut field);
+    assert!(state.cancel_word(&mut field));
+    assert_eq!((field.document.as_str(), field.preedit.as_str()), ("আমি kor", ""));
+    assert!(!state.cancel_word(&mut field));
+}
+
+#[test]
+fn history_spellings_are_shown_and_committed() {
+    let keyboard = BengaliKeyboard::new();
+    let mut history = History::new();
+    history.record("ami", "আমিই");
+    let mut state = preedit_state();
+    let mut field = TextField::new();
+    for ch in "ami".chars() {
+        assert!(state.process_key(&keyboard, &history, ch, false, &mut field));
+    }
+    assert_eq!(field.preedit, "আমিই");
+    assert!(state.process_key(&keyboard, &history, ' ', false, &mut field));
+    assert_eq!(field.document, "আমিই ");
+}
+
+#[test]
+fn direct_cancel_retypes_the_roman_keys() {
+    let keyboard = BengaliKeyboard::new();
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    assert_eq!(state.strategy, Strategy::Direct);
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new("ami"), &mut field);
+    assert_eq!(field.document, "আমি");
+    assert!(state.cancel_word(&mut field));
+    assert_eq!(field.document, "ami");
+    assert_eq!(field.preedit, "");
+}
@@ -0,0 +1,52 @@
+//! Keystroke scripts replayed through the state machine into a simulated
+//! text field.
+
+use bengali_keyboard::{BengaliKeyboard, KeyboardState, ScriptedKeys, TextField};
+
+fn type_script(script: &str) -> TextField {
+    let keyboard = BengaliKeyboard::new();
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new(script), &mut field);
+    field
+}
+
+#[test]
+fn words_and_spaces() {
+    assert_eq!(type_script("amar sOnar bangla").document, "আমার সোনার বাংলা");
+}
+
+#[test]
+fn backspace_inside_a_word_reconverts() {
+    assert_eq!(type_script("amarr\x08 bhai").document, "আমার ভাই");
+    assert_eq!(type_script("ka\x08\x08ma").document, "মা");
+}
+
+#[test]
+fn backspace_after_a_word_reaches_the_application() {
+    assert_eq!(type_script("ami \x08\x08").document, "আম");
+}
+
+#[test]
+fn digits_and_dari() {
+    assert_eq!(type_script("2024 sal.").document, "২০২৪ সাল।");
+}
+
+#[test]
+fn disabled_keyboard_passes_keys_through() {
+    let keyboard = BengaliKeyboard::new();
+    let mut state = KeyboardState::new();
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new("amar"), &mut field);
+    assert_eq!(field.document, "amar");
+}
+
+#[test]
+fn edits_are_incremental() {
+    let field = type_script("bangladesh");
+    assert_eq!(field.document, "বাংলাদেশ");
+    // Far fewer chars than retyping the whole word on every key
+    assert!(field.chars_sent < 20, "sent {} chars", field.chars_sent);
+}
@@ -0,0 +1,50 @@
+//! Reverse transliteration: Bengali back to roman keys.
+
+use bengali_keyboard::{BengaliKeyboard, FixedLayout};
+
+const WORDS: &str = include_str!("data/words-bn.txt");
+
+fn words() -> impl Iterator<Item = &'static str> {
+    WORDS.lines().filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[test]
+fn round_trips_the_corpus() {
+    let keyboard = BengaliKeyboard::new();
+    for word in words() {
+        let roman = keyboard.reverse(word).unwrap_or_else(|| panic!("cannot reverse {word}"));
+        assert_eq!(keyboard.convert_text(&roman), word, "{word} reversed to {roman}");
+    }
+}
+
+#[test]
+fn gives_the_shortest_input() {
+    let keyboard = BengaliKeyboard::new();
+    let cases = [
+        ("আমার", "amar"),
+        ("তোমার", "tOmar"),
+        ("ক্ষমা", "kShoma"),
+        ("চাঁদ", "ca.nd"),
+        // Letters that would join are kept apart, by a vowel where it is silent
+        ("বন্ধু", "bondhu"),
+        ("কর্ম", "korm"),
+    ];
+    for (bengali, roman) in cases {
+        assert_eq!(keyboard.reverse(bengali).as_deref(), Some(roman), "{bengali}");
+    }
+}
+
+#[test]
+fn sentences_keep_spacing_and_escape_english() {
+    let keyboard = BengaliKeyboard::new();
+    let text = "আমি Rust শিখি, তুমি?";
+    let roman = keyboard.reverse(text).unwrap();
+    assert_eq!(roman, "ami `Rust` Sikhi, tumi?");
+    assert_eq!(keyboard.convert_text(&roman), text);
+}
+
+#[test]
+fn fixed_layouts_are_not_reversed() {
+    let keyboard = BengaliKeyboard::with_fixed_layout(FixedLayout::builtin("bijoy").unwrap());
+    assert_eq!(keyboard.reverse("আমি"), None);
+}
@@ -0,0 +1,73 @@
+//! ISO 15919 and ALA-LC romanization.
+
+use bengali_keyboard::romanize::{from_roman, romanize, Scheme};
+
+const WORDS: &str = include_str!("data/words-bn.txt");
+
+fn words() -> impl Iterator<Item = &'static str> {
+    WORDS.lines().filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[test]
+fn writes_iso_15919() {
+    let cases = [
+        ("আমার সোনার বাংলা", "āmāra sōnāra bāṁlā"),
+        ("কৃষ্ণ", "kr̥ṣṇa"),
+        ("বাড়ি", "bāṛi"),
+        ("উৎসব", "utsaba"),
+        ("চাঁদ", "cām̐da"),
+        ("দুঃখ", "duḥkha"),
+        ("জ্ঞান", "jñāna"),
+        ("হাওয়া", "hāōẏā"),
+        ("১৯৭১।", "1971."),
+    ];
+    for (bengali, roman) in cases {
+        assert_eq!(romanize(bengali, Scheme::Iso15919), roman, "{bengali}");
+    }
+}
+
+#[test]
+fn writes_ala_lc() {
+    let cases = [
+        ("আমার সোনার বাংলা", "āmāra sonāra bāṃlā"),
+        ("কৃষ্ণ", "kṛṣṇa"),
+        ("ঋতু", "ṛtu"),
+        ("বাড়ি", "bāṛi"),
+        ("তোমার", "tomāra"),
+    ];
+    for (bengali, roman) in cases {
+        assert_eq!(romanize(bengali, Scheme::AlaLc), roman, "{bengali}");
+    }
+}
+
+#[test]
+fn separates_letters_that_would_run_together() {
+    // ক্হ against খ, অই against ঐ
+    assert_eq!(romanize("ক্হ", Scheme::Iso15919), "k:ha");
+    assert_eq!(romanize("কই", Scheme::Iso15919), "ka:i");
+    assert_eq!(romanize("কৈ", Scheme::Iso15919), "kai");
+    assert_eq!(romanize("কই", Scheme::AlaLc), "kaʹi");
+    assert_eq!(from_roman("k:ha", Scheme::Iso15919).as_deref(), Some("ক্হ"));
+    assert_eq!(from_roman("ka:i", Scheme::Iso15919).as_deref(), Some("কই"));
+}
+
+#[test]
+fn reads_romanized_text_back() {
+    assert_eq!(from_roman("Āmāra sōnāra bāṁlā", Scheme::Iso15919).as_deref(), Some("আমার সোনার বাংলা"));
+    assert_eq!(from_roman("1971.", Scheme::Iso15919).as_deref(), Some("1971।"));
+    // Letters neither scheme uses
+    assert_eq!(from_roman("amar", Scheme::Iso15919).as_deref(), Some("অমর্"));
+    assert_eq!(from_roman("sonar", Scheme::Iso15919), None);
+    assert_eq!(from_roman("kwa", Scheme::AlaLc), None);
+}
+
+#[test]
+fn round_trips_the_corpus() {
+    for scheme in [Scheme::Iso15919, Scheme::AlaLc] {
+        // Hasanta after a vowel (অ্যাপ) has no romanization
+        for word in words().filter(|word| !word.contains("অ্")) {
+            let roman = romanize(word, scheme);
+            assert_eq!(from_roman(&roman, scheme).as_deref(), Some(word), "{scheme:?}: {word} as {roman}");
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Table-driven checks of hasanta joining and the contextual rule layer.
+
+use bengali_keyboard::BengaliKeyboard;
+
+fn check(cases: &[(&str, &str)]) {
+    let keyboard = BengaliKeyboard::new();
+    for &(input, expected) in cases {
+        assert_eq!(keyboard.convert_text(input), expected, "input: {input:?}");
+    }
+}
+
+#[test]
+fn consonant_clusters_join_with_hasanta() {
+    check(&[
+        ("ndr", "ন্দ্র"),
+        ("str", "স্ত্র"),
+        ("ntr", "ন্ত্র"),
+        ("kShN", "ক্ষ্ণ"),
+        ("kt", "ক্ত"),
+        ("kot", "কত"),
+        ("k+t", "কত"),
+        ("n+d+r", "নদর"),
+        ("k+i", "কই"),
+        // With nothing to separate, + types nothing
+        ("k+", "ক"),
+    ]);
+}
+
+#[test]
+fn double_comma_types_a_visible_hasanta() {
+    check(&[
+        ("k,,", "ক্"),
+        ("rk,,", "র্ক্"),
+        // An explicit হসন্ত is not doubled by the joining
+        ("k,,t", "ক্ত"),
+    ]);
+}
+
+#[test]
+fn reph_and_ra_phala() {
+    check(&[
+        ("rk", "র্ক"),
+        ("karjo", "কার্জ"),
+        ("sorbo", "সর্ব"),
+        ("kr", "ক্র"),
+        ("gram", "গ্রাম"),
+        ("pr", "প্র"),
+    ]);
+}
+
+#[test]
+fn ya_phala() {
+    check(&[
+        ("by", "ব্য"),
+        ("byobosa", "ব্যবসা"),
+        ("kyamera", "ক্যামেরা"),
+        ("aya", "অ্যা"),
+        ("ayasiD", "অ্যাসিড"),
+        ("amay", "আমায়"),
+        ("yO", "য়ো"),
+    ]);
+}
+
+#[test]
+fn ba_phala_and_w() {
+    check(&[
+        ("dw", "দ্ব"),
+        ("bishwas", "বিশ্বাস"),
+        ("w", "ও"),
+        ("wa", "ওয়া"),
+    ]);
+}
@@ -0,0 +1,48 @@
+//! Candidate spellings ranked against a dictionary.
+
+use bengali_keyboard::{BengaliKeyboard, Dictionary, FixedLayout};
+
+#[test]
+fn confusable_sounds_find_dictionary_words() {
+    let keyboard = BengaliKeyboard::new();
+    let dictionary = Dictionary::builtin();
+    let cases = [("shokal", "সকাল"), ("bishwas", "বিশ্বাস"), ("jatiyo", "জাতীয়"), ("bari", "বাড়ি")];
+    for (roman, word) in cases {
+        let candidates = keyboard.candidates(roman, &dictionary);
+        assert_eq!(candidates.first().map(String::as_str), Some(word), "{roman}: {candidates:?}");
+    }
+}
+
+#[test]
+fn ranked_by_frequency_then_the_typed_conversion() {
+    let keyboard = BengaliKeyboard::new();
+    let dictionary = Dictionary::parse("সকাল 10\nশকাল 50\n").unwrap();
+    assert_eq!(keyboard.candidates("shokal", &dictionary), ["শকাল", "সকাল"]);
+
+    let dictionary = Dictionary::parse("সকাল\n").unwrap();
+    assert_eq!(keyboard.candidates("shokal", &dictionary), ["সকাল", "শকাল"]);
+    // Nothing known: only the plain conversion
+    assert_eq!(keyboard.candidates("xyz", &dictionary), [keyboard.convert_text("xyz")]);
+    assert!(keyboard.candidates("", &dictionary).is_empty());
+}
+
+#[test]
+fn escapes_and_fixed_layouts_are_not_respelled() {
+    let dictionary = Dictionary::builtin();
+    let keyboard = BengaliKeyboard::new();
+    assert_eq!(keyboard.candidates("\\shokal", &dictionary), ["shokal"]);
+
+    let bijoy = BengaliKeyboard::with_fixed_layout(FixedLayout::builtin("bijoy").unwrap());
+    assert_eq!(bijoy.candidates("jk", &dictionary), [bijoy.convert_text("jk")]);
+}
+
+#[test]
+fn dictionary_files() {
+    let dictionary = Dictionary::parse("# comment\n\nআমি\t5\nআমি 2\nতুমি\n").unwrap();
+    assert_eq!(dictionary.count("আমি"), Some(7));
+    assert_eq!(dictionary.count("তুমি"), Some(1));
+    assert_eq!(dictionary.len(), 2);
+
+    let error = Dictionary::parse("আমি\nতুমি many\n").unwrap_err();
+    assert_eq!(error.to_string(), "invalid dictionary, line 2: \"many\" is not a count");
+}
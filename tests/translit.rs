@@ -0,0 +1,142 @@
+//! The `bengali-translit` command line tool.
+
+use bengali_keyboard::History;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// A config directory of the tests' own, empty unless a test fills it.
+fn config_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("translit-{name}-{}", std::process::id()))
+}
+
+fn translit(args: &[&str], stdin: &str) -> Output {
+    translit_with_config(&config_dir("empty"), args, stdin)
+}
+
+fn translit_with_config(dir: &std::path::Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bengali-translit"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn converts_stdin_line_by_line() {
+    let output = translit(&[], "amar sOnar bangla\nami tOmay bhalObasi\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "আমার সোনার বাংলা\nআমি তোমায় ভালোবাসি\n");
+}
+
+#[test]
+fn uses_the_users_autocorrect_list() {
+    let dir = config_dir("autocorrect");
+    std::fs::create_dir_all(dir.join("bengali-keyboard")).unwrap();
+    let autocorrect = dir.join("bengali-keyboard/autocorrect.toml");
+    std::fs::write(autocorrect, "[words]\nami = \"আমিই\"\n").unwrap();
+    let output = translit_with_config(&dir, &[], "ami `ami` ami.\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success());
+    // Only whole words are corrected
+    assert_eq!(stdout(&output), "আমিই ami আমি।\n");
+}
+
+#[test]
+fn uses_the_users_history() {
+    let dir = config_dir("history-use");
+    std::fs::create_dir_all(dir.join("bengali-keyboard")).unwrap();
+    let mut history = History::new();
+    history.record("shokal", "শকাল");
+    history.save(dir.join("bengali-keyboard/history.txt")).unwrap();
+    let output = translit_with_config(&dir, &[], "shokal hoy\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "শকাল হয়\n");
+}
+
+#[test]
+fn preserved_segments_pass_through() {
+    let output = translit(&["--preserve", "{}"], "ami {Rust} likhi\n{multi\nline} sheSh\n");
+    assert_eq!(stdout(&output), "আমি Rust লিখি\nmulti\nline শেষ\n");
+}
+
+#[test]
+fn reads_files() {
+    let path = std::env::temp_dir().join(format!("translit-test-{}.txt", std::process::id()));
+    std::fs::write(&path, "bangladesh\n").unwrap();
+    let output = translit(&[path.to_str().unwrap()], "");
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(stdout(&output), "বাংলাদেশ\n");
+}
+
+#[test]
+fn unknown_layout_is_an_error() {
+    for layout in ["qwerty", "avro", "user"] {
+        let output = translit(&["--layout", layout], "");
+        assert!(!output.status.success(), "{layout}");
+    }
+}
+
+#[test]
+fn imports_avro_grammar_and_reports_gaps() {
+    let grammar = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/avro-phonetic.json");
+    let output = translit(&["--layout", grammar], "shundor chondro\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "শুন্দর চন্দ্র\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be expressed"));
+}
+
+#[test]
+fn fixed_layouts_by_name() {
+    let output = translit(&["--layout", "bijoy"], "cjf gf\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "কো আ\n");
+}
+
+#[test]
+fn reverses_bengali_to_roman() {
+    let output = translit(&["--reverse"], "আমার সোনার বাংলা\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "amar sOnar bangla\n");
+}
+
+#[test]
+fn romanizes_bengali() {
+    let output = translit(&["--romanize", "iso15919"], "আমার সোনার বাংলা\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "āmāra sōnāra bāṁlā\n");
+}
+
+#[test]
+fn exports_imports_and_clears_the_history() {
+    let dir = config_dir("history-manage");
+    let export = dir.join("export.txt");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bengali-translit"))
+            .args(args)
+            .env("XDG_CONFIG_HOME", &dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    };
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("import.txt"), "shokal\tশকাল\n").unwrap();
+    run(&["--import-history", dir.join("import.txt").to_str().unwrap()]);
+    run(&["--export-history", export.to_str().unwrap()]);
+    assert_eq!(std::fs::read_to_string(&export).unwrap(), "shokal\tশকাল\n");
+
+    run(&["--clear-history"]);
+    run(&["--export-history", export.to_str().unwrap()]);
+    assert_eq!(std::fs::read_to_string(&export).unwrap(), "");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
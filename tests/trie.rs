@@ -0,0 +1,44 @@
+//! The prefix trie behind keymap matching.
+
+use bengali_keyboard::trie::Trie;
+
+fn chars(text: &str) -> Vec<char> {
+    text.chars().collect()
+}
+
+fn trie(keys: &[&str]) -> Trie<String> {
+    let mut trie = Trie::new();
+    for key in keys {
+        trie.entry_or_insert_with(key, || key.to_string());
+    }
+    trie
+}
+
+#[test]
+fn longest_key_wins() {
+    let trie = trie(&["k", "kh", "khh", "a"]);
+    let input = chars("khha");
+    assert_eq!(trie.longest_match(&input), Some((&"khh".to_string(), 3)));
+    // Cutting the input short finds the shorter keys
+    assert_eq!(trie.longest_match(&input[..2]), Some((&"kh".to_string(), 2)));
+    assert_eq!(trie.longest_match(&input[..1]), Some((&"k".to_string(), 1)));
+    assert_eq!(trie.longest_match(&input[3..]), Some((&"a".to_string(), 1)));
+}
+
+#[test]
+fn inner_nodes_are_not_keys() {
+    // "ch" only leads on to "chh"
+    let trie = trie(&["chh", "x"]);
+    assert_eq!(trie.longest_match(&chars("ch")), None);
+    assert_eq!(trie.longest_match(&chars("chx")), None);
+    assert_eq!(trie.longest_match(&chars("chhx")), Some((&"chh".to_string(), 3)));
+    assert_eq!(trie.longest_match(&[]), None);
+}
+
+#[test]
+fn existing_entries_are_reused() {
+    let mut trie: Trie<Vec<u32>> = Trie::new();
+    trie.entry_or_insert_with("ng", Vec::new).push(1);
+    trie.entry_or_insert_with("ng", || panic!("already present")).push(2);
+    assert_eq!(trie.longest_match(&chars("nga")), Some((&vec![1, 2], 2)));
+}
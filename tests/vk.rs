@@ -0,0 +1,68 @@
+//! Virtual-key translation on a US keyboard.
+
+use bengali_keyboard::vk::{translate_vk, Modifiers};
+use bengali_keyboard::BACKSPACE;
+
+const NONE: Modifiers = Modifiers { shift: false, caps_lock: false };
+const SHIFT: Modifiers = Modifiers { shift: true, caps_lock: false };
+const CAPS: Modifiers = Modifiers { shift: false, caps_lock: true };
+const SHIFT_CAPS: Modifiers = Modifiers { shift: true, caps_lock: true };
+
+#[test]
+fn us_layout_table() {
+    #[rustfmt::skip]
+    let table: &[(u32, Modifiers, Option<char>)] = &[
+        // Letters: Shift and CapsLock each uppercase, together they cancel
+        (0x41, NONE, Some('a')),
+        (0x41, SHIFT, Some('A')),
+        (0x41, CAPS, Some('A')),
+        (0x41, SHIFT_CAPS, Some('a')),
+        (0x5A, SHIFT, Some('Z')),
+        (0x49, SHIFT, Some('I')),
+        // Digits and their shifted symbols; CapsLock leaves them alone
+        (0x30, NONE, Some('0')),
+        (0x30, SHIFT, Some(')')),
+        (0x34, SHIFT, Some('$')),
+        (0x34, CAPS, Some('4')),
+        (0x39, SHIFT, Some('(')),
+        // Punctuation
+        (0xBA, NONE, Some(';')),
+        (0xBA, SHIFT, Some(':')),
+        (0xBB, SHIFT, Some('+')),
+        (0xBC, NONE, Some(',')),
+        (0xBD, NONE, Some('-')),
+        (0xBD, SHIFT, Some('_')),
+        (0xBE, NONE, Some('.')),
+        (0xBE, CAPS, Some('.')),
+        (0xBF, SHIFT, Some('?')),
+        (0xC0, NONE, Some('`')),
+        (0xDB, SHIFT, Some('{')),
+        (0xDC, NONE, Some('\\')),
+        (0xDD, NONE, Some(']')),
+        (0xDE, SHIFT, Some('"')),
+        // Editing keys
+        (0x08, SHIFT, Some(BACKSPACE)),
+        (0x09, NONE, Some('\t')),
+        (0x0D, NONE, Some('\n')),
+        (0x20, SHIFT, Some(' ')),
+        // No character: F10, Shift, arrow keys
+        (0x79, NONE, None),
+        (0x10, SHIFT, None),
+        (0x25, NONE, None),
+    ];
+
+    for &(vk, modifiers, expected) in table {
+        assert_eq!(translate_vk(vk, modifiers), expected, "vk {vk:#04x} with {modifiers:?}");
+    }
+}
+
+#[test]
+fn uppercase_patterns_are_typeable() {
+    let keyboard = bengali_keyboard::BengaliKeyboard::new();
+    // t O m a r with Shift held for the O
+    let typed: String = [(0x54, NONE), (0x4F, SHIFT), (0x4D, NONE), (0x41, NONE), (0x52, NONE)]
+        .into_iter()
+        .filter_map(|(vk, modifiers)| translate_vk(vk, modifiers))
+        .collect();
+    assert_eq!(keyboard.convert_text(&typed), "তোমার");
+}
@@ -0,0 +1,71 @@
+//! Everyday words, as typed phonetically, and the Bengali they must produce.
+//!
+//! Like Avro Phonetic, `o` after a consonant is the inherent vowel and
+//! writes nothing, so ও-কার is typed with `O` (tOmar, achhO). The common
+//! spellings tomar and achho are in the autocorrect list instead.
+
+use bengali_keyboard::{Autocorrect, BengaliKeyboard, KeyboardState, ScriptedKeys, TextField};
+
+const WORDS: &[(&str, &str)] = &[
+    ("amar", "আমার"),
+    ("tOmar", "তোমার"),
+    ("bangla", "বাংলা"),
+    ("kemon", "কেমন"),
+    ("achhO", "আছো"),
+    ("ami", "আমি"),
+    ("tumi", "তুমি"),
+    ("aponi", "আপনি"),
+    ("bhalO", "ভালো"),
+    ("ei", "এই"),
+    ("oi", "ঐ"),
+    ("kintu", "কিন্তু"),
+    ("desh", "দেশ"),
+    ("ghor", "ঘর"),
+    ("khub", "খুব"),
+    ("bondhu", "বন্ধু"),
+    ("ciThi", "চিঠি"),
+    ("b+i", "বই"),
+    ("ma", "মা"),
+    ("baba", "বাবা"),
+];
+
+#[test]
+fn everyday_words() {
+    let keyboard = BengaliKeyboard::new();
+    for &(input, expected) in WORDS {
+        assert_eq!(keyboard.convert_text(input), expected, "input: {input:?}");
+    }
+}
+
+#[test]
+fn everyday_spellings_are_autocorrected() {
+    let mut keyboard = BengaliKeyboard::new();
+    keyboard.set_autocorrect(Autocorrect::builtin());
+    let mut state = KeyboardState::new();
+    state.enabled = true;
+    let mut field = TextField::new();
+    state.replay(&keyboard, &mut ScriptedKeys::new("tomar kemon achho "), &mut field);
+    assert_eq!(field.document, "তোমার কেমন আছো ");
+}
+
+#[test]
+fn vowels_are_independent_at_word_start_and_after_vowels() {
+    let keyboard = BengaliKeyboard::new();
+    assert_eq!(keyboard.convert_text("a"), "আ");
+    assert_eq!(keyboard.convert_text("ai"), "আই");
+    assert_eq!(keyboard.convert_text("ma"), "মা");
+    assert_eq!(keyboard.convert_text("mai"), "মাই");
+    assert_eq!(keyboard.convert_text("ami tumi"), "আমি তুমি");
+}
+
+#[test]
+fn every_prefix_converts_on_its_own() {
+    // The Windows front-end retypes convert_text(buffer) after each key, so
+    // each intermediate buffer must convert without reference to the rest.
+    let keyboard = BengaliKeyboard::new();
+    let steps: Vec<String> = ["a", "am", "ama", "amar"]
+        .iter()
+        .map(|prefix| keyboard.convert_text(prefix))
+        .collect();
+    assert_eq!(steps, ["আ", "আম", "আমা", "আমার"]);
+}